@@ -1,3 +1,5 @@
+pub mod lyrics;
 pub mod spotify_me;
 
+pub use lyrics::*;
 pub use spotify_me::*;
@@ -0,0 +1,189 @@
+/// Lyrics synced to the track, parsed from the LRC format
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Lyrics {
+    /// Sorted by when they start
+    pub lines: Vec<LyricLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LyricLine {
+    /// Milliseconds into the track
+    pub start_ms: u32,
+    /// Empty for a break between lines
+    pub text: String,
+}
+
+impl Lyrics {
+    /// Lines like `[01:23.45]text`, where a line can start with several times to repeat it
+    ///
+    /// Other tags like `[ar:Artist]` are skipped except for `[offset:+500]`, which moves every
+    /// line earlier by that many milliseconds. Lines without a time are skipped, so plain lyrics
+    /// end up empty.
+    pub fn parse(lrc: &str) -> Self {
+        let mut offset = 0i64;
+        let mut lines = Vec::new();
+
+        for line in lrc.lines() {
+            let mut rest = line.trim();
+            let mut starts = Vec::new();
+
+            while let Some(tag) = rest.strip_prefix('[') {
+                let Some(end) = tag.find(']') else {
+                    break;
+                };
+                rest = &tag[end + 1..];
+                let tag = &tag[..end];
+
+                match parse_time(tag) {
+                    Some(start) => starts.push(start),
+                    None => {
+                        if let Some(value) = tag.strip_prefix("offset:") {
+                            offset = value.trim().parse().unwrap_or(0);
+                        }
+                    }
+                }
+            }
+
+            for start in starts {
+                lines.push((start, rest.trim().to_string()));
+            }
+        }
+
+        // Offset applies to the whole file, wherever it is
+        let mut lines = lines
+            .into_iter()
+            .map(|(start, text)| LyricLine {
+                start_ms: (start - offset).clamp(0, u32::MAX as i64) as u32,
+                text,
+            })
+            .collect::<Vec<_>>();
+        lines.sort_by_key(|line| line.start_ms);
+
+        Self { lines }
+    }
+
+    /// Index of the line being sung `progress_ms` into the track, `None` before the first one
+    pub fn line_at(&self, progress_ms: u32) -> Option<usize> {
+        self.lines
+            .partition_point(|line| line.start_ms <= progress_ms)
+            .checked_sub(1)
+    }
+}
+
+/// `mm:ss`, `mm:ss.xx` or `mm:ss.xxx` in milliseconds
+fn parse_time(tag: &str) -> Option<i64> {
+    let (minutes, seconds) = tag.split_once(':')?;
+    let (seconds, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+    if !minutes.bytes().all(|b| b.is_ascii_digit())
+        || !seconds.bytes().all(|b| b.is_ascii_digit())
+        || !fraction.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let fraction_ms = match fraction.len() {
+        0 => 0,
+        1 => fraction.parse::<i64>().ok()? * 100,
+        2 => fraction.parse::<i64>().ok()? * 10,
+        _ => fraction[..3].parse::<i64>().ok()?,
+    };
+    Some(minutes.parse::<i64>().ok()? * 60_000 + seconds.parse::<i64>().ok()? * 1000 + fraction_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn starts(lyrics: &Lyrics) -> Vec<(u32, &str)> {
+        lyrics
+            .lines
+            .iter()
+            .map(|line| (line.start_ms, line.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn repeats_lines_with_several_times() {
+        let lyrics = Lyrics::parse("[00:10.00][01:20.00]Chorus\n[00:30.00]Verse");
+
+        assert_eq!(
+            starts(&lyrics),
+            [(10_000, "Chorus"), (30_000, "Verse"), (80_000, "Chorus")]
+        );
+    }
+
+    #[test]
+    fn parses_every_time_precision() {
+        let lyrics = Lyrics::parse(
+            "[01:02]Seconds\n[01:03.4]Tenths\n[01:04.56]Hundredths\n[01:05.789]Milliseconds",
+        );
+
+        assert_eq!(
+            starts(&lyrics),
+            [
+                (62_000, "Seconds"),
+                (63_400, "Tenths"),
+                (64_560, "Hundredths"),
+                (65_789, "Milliseconds"),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_metadata_tags() {
+        let lyrics = Lyrics::parse(
+            "[ar:Artist]\n[ti:Title]\n[al:Album]\n[by:Someone]\n[length:03:20]\n[00:01.00]First",
+        );
+
+        assert_eq!(starts(&lyrics), [(1_000, "First")]);
+    }
+
+    #[test]
+    fn moves_lines_by_offset() {
+        let lyrics = Lyrics::parse("[00:01.00]First\n[00:02.00]Second\n[offset:+1500]");
+
+        assert_eq!(starts(&lyrics), [(0, "First"), (500, "Second")]);
+    }
+
+    #[test]
+    fn sorts_lines() {
+        let lyrics = Lyrics::parse("[00:30.00]Third\n[00:10.00]First\n[00:20.00]Second");
+
+        assert_eq!(
+            starts(&lyrics),
+            [(10_000, "First"), (20_000, "Second"), (30_000, "Third")]
+        );
+    }
+
+    #[test]
+    fn keeps_timed_breaks_and_skips_blank_lines() {
+        let lyrics = Lyrics::parse("\n[00:01.00]First\n\n   \n[00:02.00]\n[00:03.00]Third\n");
+
+        assert_eq!(
+            starts(&lyrics),
+            [(1_000, "First"), (2_000, ""), (3_000, "Third")]
+        );
+    }
+
+    #[test]
+    fn plain_lyrics_are_empty() {
+        assert_eq!(
+            Lyrics::parse("Just words\nwithout any times"),
+            Lyrics::default()
+        );
+    }
+
+    #[test]
+    fn finds_line_at_progress() {
+        let lyrics = Lyrics::parse("[00:10.00]First\n[00:20.00]Second\n[00:30.00]Third");
+
+        assert_eq!(lyrics.line_at(0), None);
+        assert_eq!(lyrics.line_at(9_999), None);
+        assert_eq!(lyrics.line_at(10_000), Some(0));
+        assert_eq!(lyrics.line_at(19_999), Some(0));
+        assert_eq!(lyrics.line_at(20_000), Some(1));
+        assert_eq!(lyrics.line_at(30_000), Some(2));
+        assert_eq!(lyrics.line_at(u32::MAX), Some(2));
+        assert_eq!(Lyrics::default().line_at(10_000), None);
+    }
+}
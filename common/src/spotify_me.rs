@@ -1,7 +1,10 @@
-use rspotify::{Context, Device, RepeatState};
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+pub use rspotify::{Context, Device, DeviceType, RepeatState, Type};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Data {
     pub short_term_top: Vec<SimpleTrack>,
@@ -27,6 +30,16 @@ pub struct SimpleTrack {
     pub duration: u32,
 }
 
+impl SimpleTrack {
+    /// Spotify ID at the end of `url`, like `4uLU6hMCjMI75M1A2tKUQC` in
+    /// `https://open.spotify.com/track/4uLU6hMCjMI75M1A2tKUQC`
+    pub fn id(&self) -> Option<&str> {
+        let url = self.url.as_deref()?;
+        let path = url.split(['?', '#']).next()?;
+        path.rsplit('/').next().filter(|id| !id.is_empty())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Playing {
@@ -36,9 +49,16 @@ pub struct Playing {
     pub shuffled: bool,
     pub playing: SimpleTrack,
     pub progress_secs: u32,
+    /// Taken as playing if my API leaves it out
+    #[serde(default = "is_playing_default")]
+    pub is_playing: bool,
+}
+
+fn is_playing_default() -> bool {
+    true
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LastPlayed {
     pub track: SimpleTrack,
@@ -46,6 +66,74 @@ pub struct LastPlayed {
     pub played_at: String,
 }
 
+impl LastPlayed {
+    /// Seconds since the Unix epoch of `played_at`, an RFC 3339 timestamp like `2024-04-07T18:25:43.511Z`
+    pub fn played_at_secs(&self) -> Option<u64> {
+        unix_secs(&self.played_at)
+    }
+
+    /// Seconds since it was played by the system clock, `None` while the clock is still behind
+    pub fn secs_ago(&self) -> Option<u64> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+        now.checked_sub(self.played_at_secs()?)
+    }
+}
+
+impl Context {
+    /// Name of the album it is played from, `None` for playlists, artists and the like
+    pub fn album_name(&self) -> Option<&str> {
+        self.name.as_deref().filter(|_| self._type == Type::Album)
+    }
+}
+
+/// Seconds since the Unix epoch of an RFC 3339 `timestamp`, `None` if it is malformed or earlier
+fn unix_secs(timestamp: &str) -> Option<u64> {
+    let (date, time) = timestamp.split_once(['T', 't'])?;
+    let mut date = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+
+    // Offset is either `Z` or like `+02:00`, fractions of a second are dropped
+    let (time, offset) = match time.find(['Z', 'z', '+', '-']) {
+        Some(i) => time.split_at(i),
+        None => (time, "Z"),
+    };
+    let time = time.split('.').next()?;
+    let mut time = time.splitn(3, ':').map(|part| part.parse::<i64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+    let offset = match offset {
+        "Z" | "z" => 0,
+        offset => {
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            let (hours, minutes) = offset[1..].split_once(':')?;
+            sign * (hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60)
+        }
+    };
+
+    // Leap seconds are allowed
+    let in_range = (1..=12).contains(&month)
+        && (1..=31).contains(&day)
+        && (0..24).contains(&hour)
+        && (0..60).contains(&minute)
+        && (0..=60).contains(&second);
+    if !in_range {
+        return None;
+    }
+
+    let secs =
+        days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second - offset;
+    u64::try_from(secs).ok()
+}
+
+/// Days from 1970-01-01 to the given date, from <http://howardhinnant.github.io/date_algorithms.html>
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
 /// Hold copies of types from `rspotify` which are needed to use my Spotify API
 mod rspotify {
     use std::collections::HashMap;
@@ -61,6 +149,9 @@ mod rspotify {
         pub external_urls: HashMap<String, String>,
         #[serde(rename = "type")]
         pub _type: Type,
+        /// Name of the playlist, album, artist or show, not part of `rspotify`, filled in by my API
+        #[serde(default)]
+        pub name: Option<String>,
     }
 
     /// Device object
@@ -139,3 +230,80 @@ mod rspotify {
         Collectionyourepisodes, // rename to collectionyourepisodes
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_days_from_the_epoch() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        assert_eq!(days_from_civil(1900, 3, 1), -25508);
+        assert_eq!(days_from_civil(2024, 4, 7), 19820);
+    }
+
+    #[test]
+    fn counts_leap_days() {
+        assert_eq!(
+            days_from_civil(2024, 2, 29) + 1,
+            days_from_civil(2024, 3, 1)
+        );
+        assert_eq!(
+            days_from_civil(2000, 2, 29) + 1,
+            days_from_civil(2000, 3, 1)
+        );
+        // Centuries are only leap years every 400 years
+        assert_eq!(
+            days_from_civil(2100, 2, 28) + 1,
+            days_from_civil(2100, 3, 1)
+        );
+    }
+
+    #[test]
+    fn parses_timestamps() {
+        assert_eq!(unix_secs("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(unix_secs("2024-02-29T12:00:00Z"), Some(1_709_208_000));
+        assert_eq!(unix_secs("2024-04-07T18:25:43Z"), Some(1_712_514_343));
+        assert_eq!(unix_secs("2024-04-07t18:25:43z"), Some(1_712_514_343));
+    }
+
+    #[test]
+    fn drops_fractions_of_a_second() {
+        assert_eq!(unix_secs("2024-04-07T18:25:43.511Z"), Some(1_712_514_343));
+        assert_eq!(
+            unix_secs("2024-04-07T18:25:43.999999Z"),
+            Some(1_712_514_343)
+        );
+    }
+
+    #[test]
+    fn applies_offsets() {
+        assert_eq!(unix_secs("2024-04-07T20:25:43+02:00"), Some(1_712_514_343));
+        assert_eq!(
+            unix_secs("2024-04-07T12:55:43.2-05:30"),
+            Some(1_712_514_343)
+        );
+        // Offset moves it to the previous day
+        assert_eq!(unix_secs("2024-03-01T01:00:00+02:00"), Some(1_709_247_600));
+    }
+
+    #[test]
+    fn rejects_malformed_timestamps() {
+        for timestamp in [
+            "",
+            "yesterday",
+            "2024-04-07",
+            "2024-04-07 18:25:43Z",
+            "2024-04T18:25:43Z",
+            "2024-04-07T18:25Z",
+            "2024-04-07T18:25:43+0200",
+            "2024-13-07T18:25:43Z",
+            "2024-04-07T24:00:00Z",
+            "2024-04-07T18:60:43Z",
+            "1969-12-31T23:59:59Z",
+        ] {
+            assert_eq!(unix_secs(timestamp), None, "{timestamp}");
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! Converts the BDF fonts in `fonts/` into glyph tables for `src/font.rs`
+//!
+//! Only the code points listed in each font's subset file are kept, so large fonts like Unifont or
+//! M+ can be used without filling the flash. Fonts taller than the size they are needed in are
+//! scaled down, so the smaller font sets don't cut them off.
+
+use std::{collections::BTreeMap, env, fmt::Write, fs, path::Path};
+
+/// (static name, BDF file, subset file, pixel size), all files are in `fonts/`
+const FONTS: &[(&str, &str, &str, u32)] = &[
+    ("FONT_CJK", "cjk.bdf", "cjk.txt", 16),
+    ("FONT_CJK_SMALL", "cjk.bdf", "cjk.txt", 12),
+    ("FONT_HANGUL", "hangul.bdf", "hangul.txt", 16),
+    ("FONT_HANGUL_SMALL", "hangul.bdf", "hangul.txt", 12),
+];
+
+/// Part of a scaled down pixel which has to be covered for it to be drawn, less than half so thin
+/// strokes don't disappear
+const SCALED_COVERAGE: f32 = 0.35;
+
+struct Glyph {
+    advance: u8,
+    width: u8,
+    height: u8,
+    x: i8,
+    y: i8,
+    rows: Vec<u8>,
+}
+
+fn main() {
+    let fonts_dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("fonts");
+    println!("cargo:rerun-if-changed={}", fonts_dir.display());
+
+    let mut out = String::new();
+    for (name, bdf, subset, size) in FONTS {
+        let read = |file: &str| {
+            fs::read_to_string(fonts_dir.join(file))
+                .unwrap_or_else(|err| panic!("can't read graphics/fonts/{file} for {name}: {err}"))
+        };
+
+        let (height, mut glyphs) = parse_bdf(&read(bdf), &parse_subset(&read(subset)));
+        if *size < height {
+            let scale = *size as f32 / height as f32;
+            for glyph in glyphs.values_mut() {
+                *glyph = scale_glyph(glyph, scale);
+            }
+        }
+
+        write_font(&mut out, name, &glyphs);
+    }
+
+    let out_path = Path::new(&env::var("OUT_DIR").unwrap()).join("fonts.rs");
+    fs::write(out_path, out).unwrap();
+}
+
+/// Lines are hex code points or ranges like `3040-309F` separated by spaces, anything after `#`
+/// is ignored
+fn parse_subset(subset: &str) -> Vec<(u32, u32)> {
+    subset
+        .lines()
+        .flat_map(|line| {
+            line.split('#')
+                .next()
+                .unwrap_or_default()
+                .split_whitespace()
+        })
+        .map(|range| {
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            (
+                u32::from_str_radix(start, 16).unwrap(),
+                u32::from_str_radix(end, 16).unwrap(),
+            )
+        })
+        .collect()
+}
+
+/// Returns the height of the font's bounding box along with the glyphs in `subset`
+fn parse_bdf(bdf: &str, subset: &[(u32, u32)]) -> (u32, BTreeMap<char, Glyph>) {
+    let mut glyphs = BTreeMap::new();
+    let mut height = 0;
+    let mut lines = bdf.lines();
+
+    while let Some(line) = lines.next() {
+        if let Some(bbx) = line.strip_prefix("FONTBOUNDINGBOX ") {
+            height = bbx.split_whitespace().nth(1).unwrap().parse().unwrap();
+        }
+        if !line.starts_with("STARTCHAR") {
+            continue;
+        }
+
+        let mut encoding = None;
+        let mut advance = 0;
+        let mut bbx = [0i32; 4];
+        let mut rows = Vec::new();
+
+        for line in lines.by_ref() {
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("ENCODING") => encoding = words.next().and_then(|e| e.parse::<u32>().ok()),
+                Some("DWIDTH") => advance = words.next().unwrap().parse().unwrap(),
+                Some("BBX") => {
+                    for value in &mut bbx {
+                        *value = words.next().unwrap().parse().unwrap();
+                    }
+                }
+                Some("BITMAP") => {}
+                Some("ENDCHAR") => break,
+                Some(row) if rows.len() < row_bytes(bbx[0]) * bbx[1] as usize => {
+                    // Rows are padded to whole bytes, which is how they are stored too
+                    rows.extend(
+                        (0..row_bytes(bbx[0]))
+                            .map(|i| u8::from_str_radix(&row[i * 2..i * 2 + 2], 16).unwrap()),
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        let Some(c) = encoding.and_then(char::from_u32) else {
+            continue;
+        };
+        if !subset
+            .iter()
+            .any(|(start, end)| (*start..=*end).contains(&(c as u32)))
+        {
+            continue;
+        }
+
+        glyphs.insert(
+            c,
+            Glyph {
+                advance,
+                width: bbx[0] as u8,
+                height: bbx[1] as u8,
+                x: bbx[2] as i8,
+                y: bbx[3] as i8,
+                rows,
+            },
+        );
+    }
+
+    (height, glyphs)
+}
+
+fn row_bytes(width: i32) -> usize {
+    (width as usize).div_ceil(8)
+}
+
+/// Shrinks `glyph` by `scale`, a pixel is drawn if enough of the pixels it covers were
+fn scale_glyph(glyph: &Glyph, scale: f32) -> Glyph {
+    let scaled = |value: f32| (value * scale).round();
+    let (width, height) = (
+        scaled(glyph.width as f32) as i32,
+        scaled(glyph.height as f32) as i32,
+    );
+    let is_set = |x: i32, y: i32| {
+        x < glyph.width as i32
+            && y < glyph.height as i32
+            && glyph.rows[y as usize * row_bytes(glyph.width as i32) + x as usize / 8]
+                & (0x80 >> (x % 8))
+                != 0
+    };
+
+    let mut rows = vec![0; row_bytes(width) * height as usize];
+    for y in 0..height {
+        let (top, bottom) = (y as f32 / scale, (y + 1) as f32 / scale);
+        for x in 0..width {
+            let (left, right) = (x as f32 / scale, (x + 1) as f32 / scale);
+
+            let mut covered = 0.;
+            for source_y in top as i32..bottom.ceil() as i32 {
+                for source_x in left as i32..right.ceil() as i32 {
+                    if is_set(source_x, source_y) {
+                        covered += (right.min(source_x as f32 + 1.) - left.max(source_x as f32))
+                            * (bottom.min(source_y as f32 + 1.) - top.max(source_y as f32));
+                    }
+                }
+            }
+
+            if covered >= SCALED_COVERAGE * (right - left) * (bottom - top) {
+                rows[y as usize * row_bytes(width) + x as usize / 8] |= 0x80 >> (x % 8);
+            }
+        }
+    }
+
+    Glyph {
+        advance: scaled(glyph.advance as f32) as u8,
+        width: width as u8,
+        height: height as u8,
+        x: scaled(glyph.x as f32) as i8,
+        y: scaled(glyph.y as f32) as i8,
+        rows,
+    }
+}
+
+fn write_font(out: &mut String, name: &str, glyphs: &BTreeMap<char, Glyph>) {
+    let mut data = Vec::new();
+
+    writeln!(out, "pub static {name}: BitmapFont = BitmapFont {{").unwrap();
+    writeln!(out, "    glyphs: &[").unwrap();
+    for (c, glyph) in glyphs {
+        writeln!(
+            out,
+            "        BitmapGlyph {{ c: '\\u{{{:x}}}', advance: {}, width: {}, height: {}, x: {}, y: {}, start: {} }},",
+            *c as u32, glyph.advance, glyph.width, glyph.height, glyph.x, glyph.y, data.len()
+        )
+        .unwrap();
+        data.extend_from_slice(&glyph.rows);
+    }
+    writeln!(out, "    ],").unwrap();
+    writeln!(out, "    data: &{data:?},").unwrap();
+    writeln!(out, "}};").unwrap();
+}
@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use embedded_graphics::{
+    pixelcolor::{Rgb565, Rgb888},
+    prelude::*,
+};
+use image::{
+    imageops::{self, FilterType},
+    ImageBuffer, Rgb,
+};
+
+use crate::{
+    dither::{Dither, Ditherer},
+    palette::{fade_target, fade_until_contrast, mix, MIN_TEXT_CONTRAST},
+};
+
+/// Size the cover is shrunk to before blurring, the blur is mostly done by scaling back up
+const BLUR_SIZE: u32 = 16;
+const BLUR_SIGMA: f32 = 1.5;
+/// How far every pixel is moved towards black, or white behind dark text, before making sure
+/// the text is readable
+const FADE: f32 = 0.4;
+
+/// Blurred and faded cover filling the whole screen, drawn behind everything else
+#[derive(Debug, Clone)]
+pub struct Backdrop {
+    pixels: Arc<[Rgb565]>,
+    size: Size,
+}
+
+impl Backdrop {
+    /// Darkens the cover enough for `text` to stay readable anywhere on it, or lightens it if
+    /// `text` is dark
+    pub fn new(image: &ImageBuffer<Rgb<u8>, Vec<u8>>, size: Size, text: Rgb565) -> Self {
+        let text = Rgb888::from(text);
+        let text = [text.r(), text.g(), text.b()];
+        let target = fade_target(text);
+
+        let small = imageops::resize(image, BLUR_SIZE, BLUR_SIZE, FilterType::Triangle);
+        let mut small = imageops::blur(&small, BLUR_SIGMA);
+        for pixel in small.pixels_mut() {
+            let faded = mix(pixel.0, target, FADE);
+            pixel.0 = fade_until_contrast(faded, text, MIN_TEXT_CONTRAST);
+        }
+
+        // Cover is square, so scale it to cover the longer side and crop the rest
+        let side = size.width.max(size.height);
+        let large = imageops::resize(&small, side, side, FilterType::Triangle);
+        let large = imageops::crop_imm(
+            &large,
+            (side - size.width) / 2,
+            (side - size.height) / 2,
+            size.width,
+            size.height,
+        )
+        .to_image();
+
+        // Smooth gradients band badly in RGB565
+        let mut ditherer = Ditherer::new(Dither::Ordered, size.width);
+        let pixels = large
+            .enumerate_pixels()
+            .map(|(x, y, pixel)| ditherer.apply(x, y, pixel.0))
+            .collect();
+
+        Self { pixels, size }
+    }
+
+    /// Colors from `start` to the end of its row, empty if `start` is outside
+    pub(crate) fn row(&self, start: Point) -> &[Rgb565] {
+        let Ok((x, y)) = <(u32, u32)>::try_from(start) else {
+            return &[];
+        };
+        if x >= self.size.width || y >= self.size.height {
+            return &[];
+        }
+
+        let start = (x + y * self.size.width) as usize;
+        &self.pixels[start..start + (self.size.width - x) as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{palette::contrast, Artwork, Theme};
+
+    /// Lower than [`MIN_TEXT_CONTRAST`] since RGB565 and dithering move the colors a little
+    const MIN_DRAWN_CONTRAST: f32 = 4.;
+    const SIZE: Size = Size::new(32, 48);
+
+    /// Stripes from black to white so there's something to fade both ways
+    fn cover() -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+        ImageBuffer::from_fn(32, 32, |x, _| Rgb([(x * 8) as u8, (x * 4) as u8, 128]))
+    }
+
+    fn assert_readable(backdrop: &Backdrop, text: Rgb565) {
+        let text = Rgb888::from(text);
+        let text = [text.r(), text.g(), text.b()];
+
+        for y in 0..SIZE.height as i32 {
+            let row = backdrop.row(Point::new(0, y));
+            assert_eq!(row.len(), SIZE.width as usize);
+
+            for (x, pixel) in row.iter().enumerate() {
+                let pixel = Rgb888::from(*pixel);
+                let pixel = [pixel.r(), pixel.g(), pixel.b()];
+                assert!(
+                    contrast(pixel, text) >= MIN_DRAWN_CONTRAST,
+                    "{pixel:?} behind {text:?} at {x}, {y}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn light_text_is_readable() {
+        let backdrop = Backdrop::new(&cover(), SIZE, Rgb565::WHITE);
+        assert_readable(&backdrop, Rgb565::WHITE);
+    }
+
+    #[test]
+    fn dark_text_is_readable() {
+        let backdrop = Backdrop::new(&cover(), SIZE, Rgb565::BLACK);
+        assert_readable(&backdrop, Rgb565::BLACK);
+    }
+
+    #[test]
+    fn dark_text_does_not_turn_everything_black() {
+        let backdrop = Backdrop::new(&cover(), SIZE, Rgb565::BLACK);
+        assert!(backdrop.pixels.iter().all(|&pixel| pixel != Rgb565::BLACK));
+    }
+
+    #[test]
+    fn follows_the_theme_text() {
+        let theme = Theme::light();
+        let artwork = Artwork::new(&cover(), Dither::None).with_backdrop(&cover(), SIZE, &theme);
+
+        assert_readable(artwork.backdrop.as_ref().unwrap(), theme.palette.text);
+    }
+}
@@ -0,0 +1,67 @@
+use embedded_graphics::{pixelcolor::Rgb565, prelude::*, primitives::Rectangle};
+
+/// What a [`CountingTarget`] has sent since the last [`CountingTarget::take_stats`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    pub pixels: usize,
+    /// Separate transfers, every single pixel drawn counts as one
+    pub transfers: usize,
+}
+
+/// Counts the pixels sent to `display`, to see how much flushing only what changed saves
+#[derive(Debug)]
+pub struct CountingTarget<D> {
+    display: D,
+    stats: FrameStats,
+}
+
+impl<D: DrawTarget<Color = Rgb565>> CountingTarget<D> {
+    pub fn new(display: D) -> Self {
+        Self {
+            display,
+            stats: FrameStats::default(),
+        }
+    }
+
+    pub fn inner(&self) -> &D {
+        &self.display
+    }
+
+    /// Stats since the last call, call once per frame
+    pub fn take_stats(&mut self) -> FrameStats {
+        std::mem::take(&mut self.stats)
+    }
+}
+
+impl<D: DrawTarget<Color = Rgb565>> OriginDimensions for CountingTarget<D> {
+    fn size(&self) -> Size {
+        self.display.bounding_box().size
+    }
+}
+
+impl<D: DrawTarget<Color = Rgb565>> DrawTarget for CountingTarget<D> {
+    type Color = Rgb565;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let stats = &mut self.stats;
+        self.display.draw_iter(pixels.into_iter().inspect(|_| {
+            stats.pixels += 1;
+            stats.transfers += 1;
+        }))
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        let visible = area.intersection(&self.display.bounding_box()).size;
+        self.stats.pixels += (visible.width * visible.height) as usize;
+        self.stats.transfers += 1;
+
+        self.display.fill_contiguous(area, colors)
+    }
+}
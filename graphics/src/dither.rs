@@ -0,0 +1,181 @@
+use embedded_graphics::pixelcolor::{Rgb565, Rgb888, RgbColor};
+
+use crate::rgb888_to_rgb565;
+
+/// How to spread the precision lost when converting RGB888 to RGB565
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Dither {
+    /// Truncate each pixel on its own, causes banding on smooth gradients
+    None,
+    /// 4x4 Bayer matrix, cheap and stable between frames
+    Ordered,
+    /// Error diffusion, best looking but needs a buffer of two rows
+    #[default]
+    FloydSteinberg,
+}
+
+/// Bits kept for red, green and blue in RGB565
+const CHANNEL_BITS: [u32; 3] = [5, 6, 5];
+
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Dithers pixels one at a time, pixels must be given in row-major order
+pub(crate) struct Ditherer {
+    dither: Dither,
+    /// Error carried to the current row, padded by one pixel on both sides
+    current: Vec<[i16; 3]>,
+    /// Error carried to the next row, padded by one pixel on both sides
+    next: Vec<[i16; 3]>,
+    row: u32,
+}
+
+impl Ditherer {
+    pub fn new(dither: Dither, width: u32) -> Self {
+        let buf_len = if dither == Dither::FloydSteinberg {
+            width as usize + 2
+        } else {
+            0
+        };
+
+        Self {
+            dither,
+            current: vec![[0; 3]; buf_len],
+            next: vec![[0; 3]; buf_len],
+            row: 0,
+        }
+    }
+
+    /// Returns the dithered color of `pixel`
+    pub fn apply(&mut self, x: u32, y: u32, pixel: [u8; 3]) -> Rgb565 {
+        match self.dither {
+            Dither::None => rgb888_to_rgb565(pixel[0], pixel[1], pixel[2]),
+            Dither::Ordered => {
+                let threshold = BAYER_4X4[(y % 4) as usize][(x % 4) as usize] as u16;
+
+                let mut result = pixel;
+                for (value, bits) in result.iter_mut().zip(CHANNEL_BITS) {
+                    // Size of one step of the truncated channel in 8 bit space
+                    let step = 1u16 << (8 - bits);
+                    *value = (*value as u16 + threshold * step / 16).min(255) as u8;
+                }
+                rgb888_to_rgb565(result[0], result[1], result[2])
+            }
+            Dither::FloydSteinberg => {
+                if y != self.row {
+                    std::mem::swap(&mut self.current, &mut self.next);
+                    self.next.fill([0; 3]);
+                    self.row = y;
+                }
+
+                let x = x as usize + 1;
+                let value: [i16; 3] =
+                    std::array::from_fn(|c| (pixel[c] as i16 + self.current[x][c]).clamp(0, 255));
+                let result = rgb888_to_rgb565(value[0] as u8, value[1] as u8, value[2] as u8);
+
+                // Error against what the panel will actually show
+                let shown = Rgb888::from(result);
+                for (c, shown) in [shown.r(), shown.g(), shown.b()].into_iter().enumerate() {
+                    let error = value[c] - shown as i16;
+                    let (right, below_left, below) =
+                        (error * 7 / 16, error * 3 / 16, error * 5 / 16);
+
+                    self.current[x + 1][c] += right;
+                    self.next[x - 1][c] += below_left;
+                    self.next[x][c] += below;
+                    // Whatever was rounded away, so small errors still add up
+                    self.next[x + 1][c] += error - right - below_left - below;
+                }
+
+                result
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::{pixelcolor::raw::RawU16, prelude::*};
+    use image::{ImageBuffer, Rgb};
+
+    use super::*;
+    use crate::{fixtures, rgb8_to_rgb565};
+
+    fn colors(bytes: &[u8]) -> Vec<Rgb565> {
+        bytes
+            .chunks_exact(2)
+            .map(|pixel| Rgb565::from(RawU16::new(u16::from_be_bytes([pixel[0], pixel[1]]))))
+            .collect()
+    }
+
+    fn assert_matches_reference(input: &str, mode: Dither, name: &str) {
+        let image = fixtures::load(&format!("dither/{input}"));
+        let size = Size::new(image.width(), image.height());
+        let dithered = colors(&rgb8_to_rgb565(&image, mode));
+
+        fixtures::assert_matches(
+            &format!("dither/{input}_{name}"),
+            &fixtures::to_image(size, &dithered),
+        );
+    }
+
+    /// Average of each channel of `image` after dithering, as the panel shows it
+    fn average(image: &ImageBuffer<Rgb<u8>, Vec<u8>>, mode: Dither) -> [f32; 3] {
+        let dithered = colors(&rgb8_to_rgb565(image, mode));
+        let mut sum = [0.; 3];
+        for color in &dithered {
+            let color = Rgb888::from(*color);
+            for (sum, value) in sum.iter_mut().zip([color.r(), color.g(), color.b()]) {
+                *sum += value as f32;
+            }
+        }
+        sum.map(|sum| sum / dithered.len() as f32)
+    }
+
+    #[test]
+    fn none_matches_references() {
+        assert_matches_reference("gradient", Dither::None, "none");
+        assert_matches_reference("cover", Dither::None, "none");
+    }
+
+    #[test]
+    fn ordered_matches_references() {
+        assert_matches_reference("gradient", Dither::Ordered, "ordered");
+        assert_matches_reference("cover", Dither::Ordered, "ordered");
+    }
+
+    #[test]
+    fn floyd_steinberg_matches_references() {
+        assert_matches_reference("gradient", Dither::FloydSteinberg, "floyd_steinberg");
+        assert_matches_reference("cover", Dither::FloydSteinberg, "floyd_steinberg");
+    }
+
+    #[test]
+    fn none_matches_the_plain_conversion() {
+        let mut ditherer = Ditherer::new(Dither::None, 256);
+        for value in 0..=255 {
+            let pixel = [value, 255 - value, value / 2];
+            assert_eq!(
+                ditherer.apply(value as u32, 0, pixel),
+                rgb888_to_rgb565(pixel[0], pixel[1], pixel[2])
+            );
+        }
+    }
+
+    #[test]
+    fn dithering_keeps_the_average_color() {
+        // Colors between two steps of RGB565, which truncating can't show
+        for pixel in [[4, 2, 4], [100, 100, 100], [189, 66, 244], [240, 241, 250]] {
+            let flat = ImageBuffer::from_pixel(32, 32, Rgb(pixel));
+
+            for mode in [Dither::Ordered, Dither::FloydSteinberg] {
+                let average = average(&flat, mode);
+                for (average, value) in average.into_iter().zip(pixel) {
+                    assert!(
+                        (average - value as f32).abs() < 1.5,
+                        "{mode:?} shows {pixel:?} as {average}"
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Reference images the tests compare what they draw with
+
+use embedded_graphics::{pixelcolor::Rgb565, prelude::*};
+use image::{ImageBuffer, Rgb};
+
+use crate::Framebuffer;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures");
+
+/// Bits each channel is shifted right by to get back to RGB565
+const SHIFTS: [u32; 3] = [3, 2, 3];
+
+/// Image in `fixtures`, `name` without the extension
+pub(crate) fn load(name: &str) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    image::open(format!("{FIXTURES}/{name}.png"))
+        .unwrap()
+        .into_rgb8()
+}
+
+/// Compares `image` with `fixtures/{name}.png` in RGB565, channels can be off by one step
+///
+/// Colors picked from covers go through floating point math, which may round to the neighbouring
+/// step on another platform.
+///
+/// Run the tests with `UPDATE_FIXTURES=1` to write the references again after changing how
+/// something looks on purpose, and check the new ones before committing them.
+pub(crate) fn assert_matches(name: &str, image: &ImageBuffer<Rgb<u8>, Vec<u8>>) {
+    let path = format!("{FIXTURES}/{name}.png");
+    if std::env::var("UPDATE_FIXTURES").is_ok_and(|var| var == "1") {
+        image.save(&path).unwrap();
+    }
+    let expected = load(name);
+
+    assert_eq!(image.dimensions(), expected.dimensions(), "size of {path}");
+    let differs = image
+        .pixels()
+        .zip(expected.pixels())
+        .filter(|(pixel, expected)| {
+            (0..3).any(|c| (pixel[c] >> SHIFTS[c]).abs_diff(expected[c] >> SHIFTS[c]) > 1)
+        })
+        .count();
+    assert_eq!(differs, 0, "pixels that differ from {path}");
+}
+
+/// Everything on `display` as it shows up on the panel
+pub(crate) fn screenshot(display: &Framebuffer) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    let size = display.bounding_box().size;
+    to_image(size, &display.save(&display.bounding_box()))
+}
+
+/// `pixels` of an image of `size` in row-major order, as they show up on the panel
+pub(crate) fn to_image(size: Size, pixels: &[Rgb565]) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    ImageBuffer::from_fn(size.width, size.height, |x, y| {
+        let color = pixels[(x + y * size.width) as usize];
+        Rgb([
+            expand(color.r(), 5),
+            expand(color.g(), 6),
+            expand(color.b(), 5),
+        ])
+    })
+}
+
+/// Channel of `bits` to 8 bits, the top bits are repeated at the bottom
+fn expand(value: u8, bits: u32) -> u8 {
+    (value << (8 - bits)) | (value >> (2 * bits - 8))
+}
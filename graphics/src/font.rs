@@ -0,0 +1,527 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use embedded_canvas::Canvas;
+use embedded_graphics::{
+    mono_font::{
+        iso_8859_1, iso_8859_2, iso_8859_5, iso_8859_7, jis_x0201, MonoFont, MonoTextStyle,
+    },
+    pixelcolor::BinaryColor,
+    prelude::*,
+    text::{Baseline, Text},
+};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{kana, translit};
+
+include!(concat!(env!("OUT_DIR"), "/fonts.rs"));
+
+/// Glyphs kept in the cache, a screen of text needs far fewer
+const GLYPH_CACHE_SIZE: usize = 512;
+
+static GLYPH_CACHE: OnceLock<Mutex<GlyphCache>> = OnceLock::new();
+
+/// Glyphs by font address and character, the least recently used one is dropped once it's full
+#[derive(Debug)]
+struct GlyphCache {
+    capacity: usize,
+    /// Each glyph with the `uses` count it was last used at
+    glyphs: HashMap<(usize, char), (Arc<Glyph>, u64)>,
+    uses: u64,
+}
+
+impl GlyphCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            glyphs: HashMap::with_capacity(capacity),
+            uses: 0,
+        }
+    }
+
+    fn get_or_insert(&mut self, key: (usize, char), create: impl FnOnce() -> Glyph) -> Arc<Glyph> {
+        self.uses += 1;
+        if let Some((glyph, last_used)) = self.glyphs.get_mut(&key) {
+            *last_used = self.uses;
+            return glyph.clone();
+        }
+
+        if self.glyphs.len() >= self.capacity {
+            let oldest = self
+                .glyphs
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| *key);
+            if let Some(oldest) = oldest {
+                self.glyphs.remove(&oldest);
+            }
+        }
+
+        let glyph = Arc::new(create());
+        self.glyphs.insert(key, (glyph.clone(), self.uses));
+        glyph
+    }
+}
+
+/// Fonts used for the track title, 14 pixels tall
+pub static TITLE_FONTS: FontSet = FontSet {
+    height: 14,
+    baseline: 11,
+    default: &[
+        Font::Mono(&iso_8859_1::FONT_7X14),
+        Font::Mono(&iso_8859_2::FONT_7X14),
+    ],
+    greek: &[Font::Mono(&iso_8859_7::FONT_7X14)],
+    cyrillic: &[Font::Mono(&iso_8859_5::FONT_7X14)],
+    kana: &[
+        Font::Bitmap(&FONT_CJK_SMALL),
+        Font::Mono(&jis_x0201::FONT_7X14),
+    ],
+    han: &[Font::Bitmap(&FONT_CJK_SMALL)],
+    hangul: &[Font::Bitmap(&FONT_HANGUL_SMALL)],
+};
+
+/// Fonts used for the artists, 13 pixels tall
+pub static ARTIST_FONTS: FontSet = FontSet {
+    height: 13,
+    baseline: 10,
+    default: &[
+        Font::Mono(&iso_8859_1::FONT_6X13),
+        Font::Mono(&iso_8859_2::FONT_6X13),
+    ],
+    greek: &[Font::Mono(&iso_8859_7::FONT_6X13)],
+    cyrillic: &[Font::Mono(&iso_8859_5::FONT_6X13)],
+    kana: &[
+        Font::Bitmap(&FONT_CJK_SMALL),
+        Font::Mono(&jis_x0201::FONT_6X13),
+    ],
+    han: &[Font::Bitmap(&FONT_CJK_SMALL)],
+    hangul: &[Font::Bitmap(&FONT_HANGUL_SMALL)],
+};
+
+/// Larger fonts for the track title, 20 pixels tall
+pub static LARGE_TITLE_FONTS: FontSet = FontSet {
+    height: 20,
+    baseline: 15,
+    default: &[
+        Font::Mono(&iso_8859_1::FONT_10X20),
+        Font::Mono(&iso_8859_2::FONT_10X20),
+    ],
+    greek: &[Font::Mono(&iso_8859_7::FONT_10X20)],
+    cyrillic: &[Font::Mono(&iso_8859_5::FONT_10X20)],
+    kana: &[Font::Bitmap(&FONT_CJK), Font::Mono(&jis_x0201::FONT_10X20)],
+    han: &[Font::Bitmap(&FONT_CJK)],
+    hangul: &[Font::Bitmap(&FONT_HANGUL)],
+};
+
+/// Larger fonts for the artists, 18 pixels tall
+pub static LARGE_ARTIST_FONTS: FontSet = FontSet {
+    height: 18,
+    baseline: 13,
+    default: &[
+        Font::Mono(&iso_8859_1::FONT_9X18),
+        Font::Mono(&iso_8859_2::FONT_9X18),
+    ],
+    greek: &[Font::Mono(&iso_8859_7::FONT_9X18)],
+    cyrillic: &[Font::Mono(&iso_8859_5::FONT_9X18)],
+    kana: &[Font::Bitmap(&FONT_CJK), Font::Mono(&jis_x0201::FONT_9X18)],
+    han: &[Font::Bitmap(&FONT_CJK)],
+    hangul: &[Font::Bitmap(&FONT_HANGUL)],
+};
+
+/// Proportional font generated from a BDF file by the build script
+#[derive(Debug)]
+pub struct BitmapFont {
+    /// Sorted by `c`
+    glyphs: &'static [BitmapGlyph],
+    /// Rows of every glyph, each padded to a whole byte with the leftmost pixel in the highest bit
+    data: &'static [u8],
+}
+
+#[derive(Debug)]
+struct BitmapGlyph {
+    c: char,
+    advance: u8,
+    width: u8,
+    height: u8,
+    /// Offset of the bottom left corner from the pen position on the baseline, up is positive
+    x: i8,
+    y: i8,
+    /// Index of the first row in [`BitmapFont::data`]
+    start: u32,
+}
+
+impl BitmapFont {
+    fn glyph(&self, c: char) -> Option<&BitmapGlyph> {
+        self.glyphs
+            .binary_search_by_key(&c, |glyph| glyph.c)
+            .ok()
+            .map(|i| &self.glyphs[i])
+    }
+}
+
+/// Source of glyphs for a [`FontSet`]
+#[derive(Debug, Clone, Copy)]
+pub enum Font {
+    Mono(&'static MonoFont<'static>),
+    Bitmap(&'static BitmapFont),
+}
+
+impl Font {
+    fn contains(&self, c: char) -> bool {
+        match self {
+            // Missing characters map to the replacement glyph, which is `?` in every built in font
+            Font::Mono(font) => {
+                c == '?' || font.glyph_mapping.index(c) != font.glyph_mapping.index('?')
+            }
+            Font::Bitmap(font) => font.glyph(c).is_some(),
+        }
+    }
+
+    /// Identifies the font in the glyph cache
+    fn key(&self) -> usize {
+        match self {
+            Font::Mono(font) => *font as *const MonoFont as usize,
+            Font::Bitmap(font) => *font as *const BitmapFont as usize,
+        }
+    }
+
+    fn rasterize(&self, c: char) -> Glyph {
+        match self {
+            Font::Mono(font) => {
+                let mut canvas = Canvas::<BinaryColor>::new(font.character_size);
+                let mut buf = [0; 4];
+                Text::with_baseline(
+                    c.encode_utf8(&mut buf),
+                    Point::zero(),
+                    MonoTextStyle::new(font, BinaryColor::On),
+                    Baseline::Top,
+                )
+                .draw(&mut canvas)
+                .unwrap();
+
+                Glyph {
+                    offset: Point::new(0, -(font.baseline as i32)),
+                    size: font.character_size,
+                    advance: font.character_size.width + font.character_spacing,
+                    pixels: canvas
+                        .pixels
+                        .iter()
+                        .map(|pixel| *pixel == Some(BinaryColor::On))
+                        .collect(),
+                }
+            }
+            Font::Bitmap(font) => {
+                let Some(glyph) = font.glyph(c) else {
+                    return Glyph::default();
+                };
+
+                let (width, height) = (glyph.width as usize, glyph.height as usize);
+                let row_bytes = width.div_ceil(8);
+                let rows = &font.data[glyph.start as usize..][..row_bytes * height];
+
+                Glyph {
+                    offset: Point::new(glyph.x as i32, -(glyph.y as i32 + height as i32)),
+                    size: Size::new(width as u32, height as u32),
+                    advance: glyph.advance as u32,
+                    pixels: (0..width * height)
+                        .map(|i| {
+                            let (x, y) = (i % width, i / width);
+                            rows[y * row_bytes + x / 8] & (0x80 >> (x % 8)) != 0
+                        })
+                        .collect(),
+                }
+            }
+        }
+    }
+}
+
+/// Fonts of one size, each script has a chain of fonts which are tried in order
+#[derive(Debug, Clone, Copy)]
+pub struct FontSet {
+    pub height: u32,
+    /// Glyphs from every font are aligned on this row
+    pub baseline: u32,
+    /// Latin and anything without its own chain
+    pub default: &'static [Font],
+    pub greek: &'static [Font],
+    pub cyrillic: &'static [Font],
+    /// Hiragana and katakana
+    pub kana: &'static [Font],
+    pub han: &'static [Font],
+    pub hangul: &'static [Font],
+}
+
+impl FontSet {
+    fn chain(&self, c: char) -> &'static [Font] {
+        match c {
+            '\u{370}'..='\u{3ff}' | '\u{1f00}'..='\u{1fff}' => self.greek,
+            '\u{400}'..='\u{52f}' => self.cyrillic,
+            '\u{3040}'..='\u{30ff}' | '\u{31f0}'..='\u{31ff}' | '\u{ff61}'..='\u{ff9f}' => {
+                self.kana
+            }
+            '\u{3000}'..='\u{303f}' | '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' => self.han,
+            '\u{1100}'..='\u{11ff}' | '\u{3130}'..='\u{318f}' | '\u{ac00}'..='\u{d7a3}' => {
+                self.hangul
+            }
+            _ => self.default,
+        }
+    }
+
+    /// Glyphs of a grapheme from the first font in its chain that has the base character
+    fn grapheme_glyphs(&self, grapheme: &str) -> Option<Vec<Arc<Glyph>>> {
+        let mut chars = grapheme.chars();
+        let base = chars.next()?;
+        let font = self
+            .chain(base)
+            .iter()
+            .chain(self.default)
+            .find(|font| font.contains(base))?;
+
+        // Combining marks are only drawn if the same font has them, they are placed by the font
+        Some(
+            std::iter::once(base)
+                .chain(chars.filter(|c| font.contains(*c)))
+                .map(|c| glyph(font, c))
+                .collect(),
+        )
+    }
+
+    /// Places every glyph of `text`, returns them with their pen position and the total width
+    fn layout(&self, text: &str) -> (Vec<(i32, Arc<Glyph>)>, u32) {
+        let mut placed = Vec::new();
+        let mut pen = 0;
+
+        for grapheme in text.graphemes(true) {
+            let glyphs = self.grapheme_glyphs(grapheme).or_else(|| {
+                // Some scripts can still be shown as something close enough
+                let fallback = fallback(grapheme)?;
+                Some(
+                    fallback
+                        .graphemes(true)
+                        .filter_map(|grapheme| self.grapheme_glyphs(grapheme))
+                        .flatten()
+                        .collect(),
+                )
+            });
+            let glyphs = glyphs.unwrap_or_else(|| {
+                // Replacement glyph of the default font
+                let c = grapheme.chars().next().unwrap_or('?');
+                vec![glyph(&self.default[0], c)]
+            });
+
+            let mut advance = 0;
+            for glyph in glyphs {
+                if glyph.advance != 0 {
+                    // Start of a new character, combining marks stay on the last one
+                    pen += advance;
+                    advance = glyph.advance as i32;
+                }
+                placed.push((pen, glyph));
+            }
+            pen += advance;
+        }
+
+        (placed, pen.max(0) as u32)
+    }
+
+    /// Width of `text` drawn with [`FontSet::render`]
+    pub fn width(&self, text: &str) -> u32 {
+        self.layout(text).1
+    }
+
+    /// Draws `text` on one line, the canvas is as wide as the text and [`FontSet::height`] tall
+    pub fn render<C: PixelColor>(&self, text: &str, color: C) -> Canvas<C> {
+        let mut canvas = Canvas::new(Size::new(self.width(text), self.height));
+        self.draw(text, Point::zero(), color, &mut canvas);
+        canvas
+    }
+
+    /// Draws `text` on one line with the top left corner of the line at `top_left`
+    pub fn draw<D: DrawTarget>(&self, text: &str, top_left: Point, color: D::Color, target: &mut D)
+    where
+        D::Error: Debug,
+    {
+        for (pen, glyph) in self.layout(text).0 {
+            // Glyphs reaching past the top or bottom of the line are moved back onto it
+            let top = (self.baseline as i32 + glyph.offset.y)
+                .min(self.height as i32 - glyph.size.height as i32)
+                .max(0);
+            let top_left = top_left + Point::new(pen + glyph.offset.x, top);
+            let width = glyph.size.width.max(1) as i32;
+
+            target
+                .draw_iter(
+                    glyph
+                        .pixels
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, on)| **on)
+                        .map(|(i, _)| {
+                            let point = Point::new(i as i32 % width, i as i32 / width);
+                            Pixel(top_left + point, color)
+                        }),
+                )
+                .unwrap();
+        }
+    }
+}
+
+/// Glyph ready to be drawn, shared through the glyph cache
+#[derive(Debug, Default)]
+struct Glyph {
+    /// Top left corner relative to the pen position on the baseline
+    offset: Point,
+    size: Size,
+    advance: u32,
+    /// Row by row, `true` where the glyph is drawn
+    pixels: Box<[bool]>,
+}
+
+fn glyph(font: &Font, c: char) -> Arc<Glyph> {
+    GLYPH_CACHE
+        .get_or_init(|| Mutex::new(GlyphCache::new(GLYPH_CACHE_SIZE)))
+        .lock()
+        .unwrap()
+        .get_or_insert((font.key(), c), || font.rasterize(c))
+}
+
+/// Replacement text for graphemes no font can draw
+fn fallback(grapheme: &str) -> Option<String> {
+    let half = kana::to_jis_x0201(grapheme);
+    if half != grapheme {
+        return Some(half);
+    }
+
+    let transliterated = translit::transliterate(grapheme);
+    (transliterated != grapheme).then_some(transliterated)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    /// Returns the glyph for `c`, counting how often it had to be created
+    fn get(cache: &mut GlyphCache, c: char, created: &Cell<u32>) -> Arc<Glyph> {
+        cache.get_or_insert((0, c), || {
+            created.set(created.get() + 1);
+            Glyph::default()
+        })
+    }
+
+    #[test]
+    fn reuses_cached_glyphs() {
+        let mut cache = GlyphCache::new(2);
+        let created = Cell::new(0);
+
+        let first = get(&mut cache, 'a', &created);
+        let second = get(&mut cache, 'a', &created);
+
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(created.get(), 1);
+    }
+
+    #[test]
+    fn drops_least_recently_used_glyph() {
+        let mut cache = GlyphCache::new(2);
+        let created = Cell::new(0);
+
+        get(&mut cache, 'a', &created);
+        get(&mut cache, 'b', &created);
+        get(&mut cache, 'a', &created);
+        // Full, `b` hasn't been used for longest
+        get(&mut cache, 'c', &created);
+        assert_eq!(created.get(), 3);
+
+        get(&mut cache, 'a', &created);
+        get(&mut cache, 'c', &created);
+        assert_eq!(created.get(), 3);
+
+        get(&mut cache, 'b', &created);
+        assert_eq!(created.get(), 4);
+        assert_eq!(cache.glyphs.len(), 2);
+    }
+
+    #[test]
+    fn text_longer_than_the_cache_keeps_the_rest() {
+        let mut cache = GlyphCache::new(4);
+        let created = Cell::new(0);
+
+        for c in "abcd".chars() {
+            get(&mut cache, c, &created);
+        }
+        for c in "abce".chars() {
+            get(&mut cache, c, &created);
+        }
+
+        // Only `e` is new, it replaces `d` instead of emptying the cache
+        assert_eq!(created.get(), 5);
+        assert!(!cache.glyphs.contains_key(&(0, 'd')));
+    }
+
+    const FONT_SETS: [&FontSet; 4] = [
+        &TITLE_FONTS,
+        &ARTIST_FONTS,
+        &LARGE_TITLE_FONTS,
+        &LARGE_ARTIST_FONTS,
+    ];
+
+    #[test]
+    fn cjk_glyphs_match_the_size_of_the_set() {
+        for text in ["東京", "あア", "한글"] {
+            assert_eq!(TITLE_FONTS.width(text), 24, "{text}");
+            assert_eq!(ARTIST_FONTS.width(text), 24, "{text}");
+            assert_eq!(LARGE_TITLE_FONTS.width(text), 32, "{text}");
+            assert_eq!(LARGE_ARTIST_FONTS.width(text), 32, "{text}");
+        }
+    }
+
+    #[test]
+    fn cjk_glyphs_are_not_cut_off() {
+        let text = "東京の夜한글";
+
+        for fonts in FONT_SETS {
+            let in_glyphs = fonts
+                .layout(text)
+                .0
+                .iter()
+                .map(|(_, glyph)| glyph.pixels.iter().filter(|on| **on).count())
+                .sum::<usize>();
+            let drawn = fonts
+                .render(text, BinaryColor::On)
+                .pixels
+                .iter()
+                .filter(|pixel| **pixel == Some(BinaryColor::On))
+                .count();
+
+            assert!(in_glyphs > 0);
+            assert_eq!(drawn, in_glyphs, "{} pixels tall", fonts.height);
+        }
+    }
+
+    #[test]
+    fn half_width_kana_fall_back_to_the_next_font() {
+        // Not in the CJK subset, but in JIS X 0201
+        assert!(!Font::Bitmap(&FONT_CJK).contains('ｱ'));
+        assert_eq!(TITLE_FONTS.width("ｱ"), 7);
+        assert_eq!(LARGE_TITLE_FONTS.width("ｱ"), 10);
+    }
+
+    #[test]
+    fn missing_characters_are_replaced() {
+        // Transliterated to `a`
+        assert_eq!(TITLE_FONTS.width("ǎ"), 7);
+        // Ideograph left out of the subset, drawn as the replacement glyph of the default font
+        assert!(!Font::Bitmap(&FONT_CJK).contains('丂'));
+        assert_eq!(TITLE_FONTS.width("丂"), 7);
+        assert_eq!(
+            TITLE_FONTS.render("丂", BinaryColor::On).pixels,
+            TITLE_FONTS.render("?", BinaryColor::On).pixels
+        );
+    }
+}
@@ -0,0 +1,722 @@
+use std::ops::Range;
+
+use embedded_graphics::{
+    pixelcolor::{raw::RawU16, Rgb565},
+    prelude::*,
+    primitives::Rectangle,
+};
+
+use crate::{Backdrop, PanelFormat};
+
+/// Whole screen of packed big endian RGB565 pixels, drawn into in memory and sent to the panel after
+/// each frame
+///
+/// Only pixels which actually changed are marked dirty. [`Framebuffer::take_dirty`] hands out one
+/// rectangle for every run of changed rows, so a scrolling title and a progress tick in the same
+/// frame don't send everything between them.
+#[derive(Debug)]
+pub struct Framebuffer {
+    bytes: Box<[u8]>,
+    size: Size,
+    /// Columns changed in each row since the last flush
+    dirty: Box<[Option<Range<u32>>]>,
+    /// Pixels of the [`Layer`] being drawn, kept around so every frame doesn't allocate it again
+    scratch: Vec<u8>,
+}
+
+impl Framebuffer {
+    /// The panel must currently be filled with `initial`
+    pub fn new(size: Size, initial: Rgb565) -> Self {
+        let pixel = initial.into_storage().to_be_bytes();
+
+        Self {
+            bytes: pixel
+                .iter()
+                .copied()
+                .cycle()
+                .take((size.width * size.height * 2) as usize)
+                .collect(),
+            size,
+            dirty: vec![None; size.height as usize].into_boxed_slice(),
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Starts drawing `area` from scratch, filled with the `backdrop` if there is one, otherwise
+    /// the `background`
+    ///
+    /// Nothing is changed before [`Layer::finish`], so pixels drawn over with the same color as
+    /// before are never flushed.
+    pub(crate) fn layer(
+        &mut self,
+        area: Rectangle,
+        background: Rgb565,
+        backdrop: Option<&Backdrop>,
+        format: PanelFormat,
+    ) -> Layer<'_> {
+        let area = area.intersection(&self.bounding_box());
+        let background = format.to_panel(background).into_storage().to_be_bytes();
+        self.scratch.clear();
+        self.scratch
+            .resize((area.size.width * area.size.height * 2) as usize, 0);
+
+        let rows = self
+            .scratch
+            .chunks_exact_mut(area.size.width.max(1) as usize * 2);
+        for (y, row) in area.rows().zip(rows) {
+            let behind = backdrop
+                .map(|backdrop| backdrop.row(Point::new(area.top_left.x, y)))
+                .unwrap_or_default();
+            for (i, pixel) in row.chunks_exact_mut(2).enumerate() {
+                pixel.copy_from_slice(&match behind.get(i) {
+                    Some(color) => format.to_panel(*color).into_storage().to_be_bytes(),
+                    None => background,
+                });
+            }
+        }
+
+        Layer {
+            framebuffer: self,
+            area,
+            format,
+        }
+    }
+
+    /// Same as [`Framebuffer::layer`], but starting with whatever is in `area` already
+    pub(crate) fn overlay(&mut self, area: Rectangle, format: PanelFormat) -> Layer<'_> {
+        let area = area.intersection(&self.bounding_box());
+        self.scratch.clear();
+        for y in area.rows() {
+            let start = self.index(Point::new(area.top_left.x, y));
+            self.scratch
+                .extend_from_slice(&self.bytes[start..start + area.size.width as usize * 2]);
+        }
+
+        Layer {
+            framebuffer: self,
+            area,
+            format,
+        }
+    }
+
+    /// Byte offset of `point`, which must be on the screen
+    fn index(&self, point: Point) -> usize {
+        ((point.x as u32 + point.y as u32 * self.size.width) * 2) as usize
+    }
+
+    fn set(&mut self, point: Point, color: Rgb565) {
+        let Ok((x, y)) = <(u32, u32)>::try_from(point) else {
+            return;
+        };
+        if x >= self.size.width || y >= self.size.height {
+            return;
+        }
+
+        let i = self.index(point);
+        let pixel = color.into_storage().to_be_bytes();
+        if self.bytes[i..i + 2] == pixel {
+            return;
+        }
+        self.bytes[i..i + 2].copy_from_slice(&pixel);
+        mark_dirty(&mut self.dirty[y as usize], x..x + 1);
+    }
+
+    /// Pixels in `area` as they are now, to be put back with [`Framebuffer::restore`] once whatever
+    /// is drawn over them is gone
+    pub fn save(&self, area: &Rectangle) -> Vec<Rgb565> {
+        area.intersection(&self.bounding_box())
+            .points()
+            .map(|point| {
+                let i = self.index(point);
+                Rgb565::from(RawU16::new(u16::from_be_bytes([
+                    self.bytes[i],
+                    self.bytes[i + 1],
+                ])))
+            })
+            .collect()
+    }
+
+    /// Puts back pixels taken by [`Framebuffer::save`] with the same `area`, only rows which
+    /// actually differ are flushed again
+    pub fn restore(&mut self, area: &Rectangle, pixels: &[Rgb565]) {
+        let area = area.intersection(&self.bounding_box());
+        for (point, color) in area.points().zip(pixels) {
+            self.set(point, *color);
+        }
+    }
+
+    /// Areas changed since the last call, each to be sent in its own transfer
+    ///
+    /// Every run of changed rows becomes one rectangle as wide as the changes in any of its rows.
+    /// Unchanged rows in between are never sent.
+    pub fn take_dirty(&mut self) -> Vec<Rectangle> {
+        let mut areas = Vec::new();
+        let mut band = None::<(u32, Range<u32>)>;
+
+        for y in 0..=self.size.height {
+            let columns = self.dirty.get_mut(y as usize).and_then(Option::take);
+            band = match (band, columns) {
+                (Some((top, band_columns)), Some(columns)) => Some((
+                    top,
+                    band_columns.start.min(columns.start)..band_columns.end.max(columns.end),
+                )),
+                (None, columns) => columns.map(|columns| (y, columns)),
+                (Some((top, columns)), None) => {
+                    areas.push(Rectangle::new(
+                        Point::new(columns.start as i32, top as i32),
+                        Size::new(columns.end - columns.start, y - top),
+                    ));
+                    None
+                }
+            };
+        }
+
+        areas
+    }
+
+    /// Appends the bytes of `area` to `bytes` row by row, ready to be sent to the panel
+    pub fn copy_area(&self, area: &Rectangle, bytes: &mut Vec<u8>) {
+        let area = area.intersection(&self.bounding_box());
+        let Some(bottom_right) = area.bottom_right() else {
+            return;
+        };
+
+        for y in area.top_left.y..=bottom_right.y {
+            let start = self.index(Point::new(area.top_left.x, y));
+            bytes.extend_from_slice(&self.bytes[start..start + area.size.width as usize * 2]);
+        }
+    }
+
+    /// Draws the changed areas to `target`, for displays without a raw byte interface like the simulator
+    pub fn flush_to<D: DrawTarget<Color = Rgb565>>(
+        &mut self,
+        target: &mut D,
+    ) -> Result<(), D::Error> {
+        let mut bytes = Vec::new();
+        for area in self.take_dirty() {
+            bytes.clear();
+            self.copy_area(&area, &mut bytes);
+            target.fill_contiguous(
+                &area,
+                bytes.chunks_exact(2).map(|pixel| {
+                    Rgb565::from(RawU16::new(u16::from_be_bytes([pixel[0], pixel[1]])))
+                }),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl OriginDimensions for Framebuffer {
+    fn size(&self) -> Size {
+        self.size
+    }
+}
+
+impl DrawTarget for Framebuffer {
+    type Color = Rgb565;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            self.set(point, color);
+        }
+
+        Ok(())
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        for (point, color) in area.points().zip(colors) {
+            self.set(point, color);
+        }
+
+        Ok(())
+    }
+}
+
+/// Adds `changed` to the dirty `columns` of a row
+fn mark_dirty(columns: &mut Option<Range<u32>>, changed: Range<u32>) {
+    *columns = Some(match columns.take() {
+        Some(columns) => columns.start.min(changed.start)..columns.end.max(changed.end),
+        None => changed,
+    });
+}
+
+/// Part of a [`Framebuffer`] drawn in memory first, in screen coordinates and logical colors
+///
+/// Created by [`Framebuffer::layer`], drawing outside of its area does nothing.
+#[derive(Debug)]
+pub struct Layer<'a> {
+    framebuffer: &'a mut Framebuffer,
+    area: Rectangle,
+    format: PanelFormat,
+}
+
+impl Layer<'_> {
+    /// Copies everything drawn to the framebuffer, only columns which changed are marked dirty
+    pub(crate) fn finish(self) {
+        let area = self.area;
+        self.finish_parts(&[area]);
+    }
+
+    /// Same as [`Layer::finish`], but only for `parts` of the area, the rest is left alone
+    pub(crate) fn finish_parts(self, parts: &[Rectangle]) {
+        let Framebuffer {
+            bytes,
+            size,
+            dirty,
+            scratch,
+        } = self.framebuffer;
+
+        for part in parts {
+            let part = part.intersection(&self.area);
+            let width = part.size.width as usize * 2;
+
+            for y in part.rows() {
+                let start = ((part.top_left.x as u32 + y as u32 * size.width) * 2) as usize;
+                let scratch_start = ((part.top_left.x - self.area.top_left.x) as u32
+                    + (y - self.area.top_left.y) as u32 * self.area.size.width)
+                    as usize
+                    * 2;
+                let row = &mut bytes[start..start + width];
+                let new = &scratch[scratch_start..scratch_start + width];
+
+                // Whole pixels from the first to the last one which differ
+                let Some(first) = row.iter().zip(new).position(|(old, new)| old != new) else {
+                    continue;
+                };
+                let last = row
+                    .iter()
+                    .zip(new)
+                    .rposition(|(old, new)| old != new)
+                    .unwrap();
+                let (first, last) = (first / 2 * 2, last / 2 * 2 + 2);
+                row[first..last].copy_from_slice(&new[first..last]);
+
+                let x = part.top_left.x as u32;
+                mark_dirty(
+                    &mut dirty[y as usize],
+                    x + first as u32 / 2..x + last as u32 / 2,
+                );
+            }
+        }
+    }
+
+    fn index(&self, point: Point) -> Option<usize> {
+        if !self.area.contains(point) {
+            return None;
+        }
+
+        let point = point - self.area.top_left;
+        Some((point.x as u32 + point.y as u32 * self.area.size.width) as usize * 2)
+    }
+}
+
+impl OriginDimensions for Layer<'_> {
+    /// Size of the whole screen, since drawing is done in screen coordinates
+    fn size(&self) -> Size {
+        self.framebuffer.size
+    }
+}
+
+impl DrawTarget for Layer<'_> {
+    type Color = Rgb565;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if let Some(i) = self.index(point) {
+                let pixel = self.format.to_panel(color).into_storage().to_be_bytes();
+                self.framebuffer.scratch[i..i + 2].copy_from_slice(&pixel);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        // Images are mostly drawn where they fit, so whole rows can be written at once
+        if self.area.intersection(area) != *area {
+            return self.draw_iter(
+                area.points()
+                    .zip(colors)
+                    .map(|(point, color)| Pixel(point, color)),
+            );
+        }
+        let Some(start) = self.index(area.top_left) else {
+            return Ok(());
+        };
+        let stride = self.area.size.width as usize * 2;
+        let width = area.size.width as usize * 2;
+        let mut colors = colors.into_iter();
+
+        for row in 0..area.size.height as usize {
+            let start = start + row * stride;
+            for (bytes, color) in self.framebuffer.scratch[start..start + width]
+                .chunks_exact_mut(2)
+                .zip(colors.by_ref())
+            {
+                bytes.copy_from_slice(&self.format.to_panel(color).into_storage().to_be_bytes());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = area.intersection(&self.area);
+        let Some(start) = self.index(area.top_left) else {
+            return Ok(());
+        };
+        let pixel = self.format.to_panel(color).into_storage().to_be_bytes();
+        let stride = self.area.size.width as usize * 2;
+
+        for row in 0..area.size.height as usize {
+            let start = start + row * stride;
+            for bytes in self.framebuffer.scratch[start..start + area.size.width as usize * 2]
+                .chunks_exact_mut(2)
+            {
+                bytes.copy_from_slice(&pixel);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{Context, Playing};
+    use embedded_graphics::primitives::PrimitiveStyle;
+
+    use super::*;
+    use crate::{
+        draw_album_cover, draw_current_name_and_artist, draw_current_progress, draw_status,
+        placeholder_cover, Artwork, Dither, Layout, MarqueeStyle, Orientation, Palette, Theme,
+        TimeLabels, TrackText,
+    };
+
+    const SIZE: Size = Size::new(8, 6);
+
+    fn draw(display: &mut Framebuffer, points: &[(i32, i32)], color: Rgb565) {
+        display
+            .draw_iter(points.iter().map(|(x, y)| Pixel(Point::new(*x, *y), color)))
+            .unwrap();
+    }
+
+    #[test]
+    fn changed_span_is_reported_once() {
+        let mut display = Framebuffer::new(SIZE, Rgb565::BLACK);
+        draw(&mut display, &[(2, 1), (5, 1)], Rgb565::RED);
+
+        assert_eq!(
+            display.take_dirty(),
+            [Rectangle::new(Point::new(2, 1), Size::new(4, 1))]
+        );
+        assert_eq!(display.take_dirty(), []);
+    }
+
+    #[test]
+    fn same_color_is_not_dirty() {
+        let mut display = Framebuffer::new(SIZE, Rgb565::BLACK);
+        draw(&mut display, &[(0, 0), (7, 5)], Rgb565::BLACK);
+
+        assert_eq!(display.take_dirty(), []);
+    }
+
+    #[test]
+    fn each_run_of_rows_is_one_area() {
+        let mut display = Framebuffer::new(SIZE, Rgb565::BLACK);
+        draw(&mut display, &[(1, 0), (3, 1), (6, 4)], Rgb565::RED);
+
+        assert_eq!(
+            display.take_dirty(),
+            [
+                Rectangle::new(Point::new(1, 0), Size::new(3, 2)),
+                Rectangle::new(Point::new(6, 4), Size::new(1, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn copies_area_row_by_row() {
+        let mut display = Framebuffer::new(SIZE, Rgb565::BLACK);
+        draw(&mut display, &[(2, 1), (3, 2)], Rgb565::RED);
+
+        let mut bytes = vec![0xAA];
+        display.copy_area(
+            &Rectangle::new(Point::new(2, 1), Size::new(2, 2)),
+            &mut bytes,
+        );
+
+        let [red, black] = [Rgb565::RED, Rgb565::BLACK].map(|c| c.into_storage().to_be_bytes());
+        assert_eq!(
+            bytes,
+            [[0xAA].as_slice(), &red, &black, &black, &red].concat()
+        );
+    }
+
+    #[test]
+    fn copies_only_what_is_on_screen() {
+        let display = Framebuffer::new(SIZE, Rgb565::BLACK);
+
+        let mut bytes = Vec::new();
+        display.copy_area(
+            &Rectangle::new(Point::new(6, 4), Size::new(10, 10)),
+            &mut bytes,
+        );
+
+        assert_eq!(bytes.len(), 2 * 2 * 2);
+    }
+
+    #[test]
+    fn layer_only_marks_what_changed() {
+        let format = PanelFormat::default();
+        let area = Rectangle::new(Point::new(1, 1), Size::new(6, 4));
+        let bar = Rectangle::new(Point::new(2, 2), Size::new(3, 1));
+        let mut display = Framebuffer::new(SIZE, Rgb565::BLACK);
+
+        let mut layer = display.layer(area, Rgb565::BLACK, None, format);
+        bar.into_styled(PrimitiveStyle::with_fill(Rgb565::RED))
+            .draw(&mut layer)
+            .unwrap();
+        layer.finish();
+        assert_eq!(display.take_dirty(), [bar]);
+
+        // Background drawn over the bar again first, which mustn't count as a change
+        let mut layer = display.layer(area, Rgb565::BLACK, None, format);
+        bar.into_styled(PrimitiveStyle::with_fill(Rgb565::RED))
+            .draw(&mut layer)
+            .unwrap();
+        layer.finish();
+        assert_eq!(display.take_dirty(), []);
+    }
+
+    #[test]
+    fn layer_parts_leave_the_rest_alone() {
+        let format = PanelFormat::default();
+        let area = Rectangle::new(Point::zero(), SIZE);
+        let top = Rectangle::new(Point::zero(), Size::new(8, 2));
+        let mut display = Framebuffer::new(SIZE, Rgb565::BLACK);
+
+        display
+            .layer(area, Rgb565::BLUE, None, format)
+            .finish_parts(&[top]);
+
+        assert_eq!(display.take_dirty(), [top]);
+        assert!(display
+            .save(&Rectangle::new(Point::new(0, 2), Size::new(8, 4)))
+            .iter()
+            .all(|color| *color == Rgb565::BLACK));
+    }
+
+    #[test]
+    fn overlay_keeps_what_is_below() {
+        let format = PanelFormat::default();
+        let area = Rectangle::new(Point::zero(), SIZE);
+        let mut display = Framebuffer::new(SIZE, Rgb565::BLACK);
+        draw(&mut display, &[(4, 4)], Rgb565::RED);
+        display.take_dirty();
+
+        let mut layer = display.overlay(area, format);
+        Pixel(Point::new(1, 1), Rgb565::GREEN)
+            .draw(&mut layer)
+            .unwrap();
+        layer.finish();
+
+        assert_eq!(
+            display.take_dirty(),
+            [Rectangle::new(Point::new(1, 1), Size::new(1, 1))]
+        );
+        assert_eq!(
+            display.save(&Rectangle::new(Point::new(4, 4), Size::new(1, 1))),
+            [Rgb565::RED]
+        );
+    }
+
+    /// Screen with a track playing, drawn like the main loop does
+    struct Playback {
+        display: Framebuffer,
+        layout: Layout,
+        theme: Theme,
+        playing: Playing,
+        palette: Palette,
+        time_labels: TimeLabels,
+    }
+
+    impl Playback {
+        fn new() -> Self {
+            let playing: Playing =
+                serde_json::from_str(include_str!("../fixtures/snapshot.json")).unwrap();
+            let theme = Theme::default();
+            let display = Framebuffer::new(Size::new(128, 160), Rgb565::BLACK);
+            let layout = Layout::new(display.bounding_box(), Orientation::Portrait, &theme);
+
+            Self {
+                palette: theme.track_palette(None),
+                display,
+                layout,
+                theme,
+                playing,
+                time_labels: TimeLabels::new(),
+            }
+        }
+
+        fn draw_everything(&mut self) {
+            let format = PanelFormat::default();
+            let cover = placeholder_cover(
+                &self.playing.playing.name,
+                self.playing.context.as_ref().and_then(Context::album_name),
+                self.layout.cover.size.width,
+            );
+            let artwork = Artwork::new(&cover, Dither::default());
+            let text = TrackText::new(
+                &self.playing,
+                MarqueeStyle::default(),
+                &self.layout,
+                &self.theme,
+            );
+
+            draw_status(
+                &mut self.display,
+                &self.layout,
+                &self.theme,
+                &self.playing,
+                true,
+                self.palette,
+                None,
+                format,
+            );
+            draw_album_cover(
+                &mut self.display,
+                &self.layout,
+                Some(&artwork.cover),
+                self.palette,
+                None,
+                format,
+            );
+            draw_current_name_and_artist(
+                &mut self.display,
+                &self.layout,
+                &text,
+                self.palette,
+                None,
+                format,
+            );
+            self.draw_progress(self.playing.progress_secs);
+        }
+
+        fn draw_progress(&mut self, progress_secs: u32) {
+            let format = PanelFormat::default();
+            let duration = self.playing.playing.duration;
+
+            draw_current_progress(
+                &mut self.display,
+                &self.layout,
+                &self.theme,
+                progress_secs,
+                duration,
+                self.palette,
+                None,
+                format,
+            );
+            self.time_labels.draw(
+                &mut self.display,
+                &self.layout,
+                &self.theme,
+                progress_secs,
+                duration,
+                self.palette,
+                None,
+                format,
+            );
+        }
+
+        fn screen(&self) -> Vec<Rgb565> {
+            self.display.save(&self.display.bounding_box())
+        }
+    }
+
+    /// Every pixel which differs between `before` and `after` is in one of `areas`, and each of
+    /// them is as small as the changed rows and columns inside it
+    fn assert_only_changes(before: &[Rgb565], after: &[Rgb565], width: u32, areas: &[Rectangle]) {
+        let changed = |point: Point| {
+            let i = (point.x as u32 + point.y as u32 * width) as usize;
+            before[i] != after[i]
+        };
+
+        for (i, _) in before
+            .iter()
+            .zip(after)
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+        {
+            let point = Point::new((i as u32 % width) as i32, (i as u32 / width) as i32);
+            assert!(
+                areas.iter().any(|area| area.contains(point)),
+                "{point} changed outside of {areas:?}"
+            );
+        }
+
+        for area in areas {
+            let bottom_right = area.bottom_right().unwrap();
+            let row_changed = |y| area.columns().any(|x| changed(Point::new(x, y)));
+            let column_changed = |x| area.rows().any(|y| changed(Point::new(x, y)));
+            assert!(
+                row_changed(area.top_left.y) && row_changed(bottom_right.y),
+                "{area:?} has unchanged rows at the edges"
+            );
+            assert!(
+                column_changed(area.top_left.x) && column_changed(bottom_right.x),
+                "{area:?} has unchanged columns at the edges"
+            );
+        }
+    }
+
+    #[test]
+    fn drawing_the_same_frame_again_sends_nothing() {
+        let mut playback = Playback::new();
+        playback.draw_everything();
+        playback.display.take_dirty();
+
+        playback.time_labels.clear();
+        playback.draw_everything();
+        assert_eq!(playback.display.take_dirty(), []);
+    }
+
+    #[test]
+    fn progress_tick_sends_only_what_changed() {
+        let mut playback = Playback::new();
+        playback.draw_everything();
+        playback.display.take_dirty();
+        let before = playback.screen();
+
+        playback.draw_progress(playback.playing.progress_secs + 5);
+        let areas = playback.display.take_dirty();
+
+        assert!(!areas.is_empty());
+        assert_only_changes(&before, &playback.screen(), 128, &areas);
+        // Progress bar and the time labels below it
+        let layout = &playback.layout;
+        let progress = Rectangle::with_corners(
+            layout.progress_area().top_left,
+            layout.time_area().bottom_right().unwrap(),
+        );
+        assert!(areas
+            .iter()
+            .all(|area| progress.intersection(area) == *area));
+    }
+}
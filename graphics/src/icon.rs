@@ -0,0 +1,239 @@
+use std::fmt::Debug;
+
+use common::{DeviceType, Type};
+use embedded_graphics::{pixelcolor::Rgb565, prelude::*};
+
+/// Icon of the kind of device playback is on
+pub(crate) fn device_icon(device: &DeviceType) -> &'static Icon {
+    match device {
+        DeviceType::Computer => &COMPUTER,
+        DeviceType::Tablet => &TABLET,
+        DeviceType::Smartphone => &SMARTPHONE,
+        DeviceType::Speaker | DeviceType::Avr | DeviceType::AudioDongle | DeviceType::CastAudio => {
+            &SPEAKER
+        }
+        DeviceType::Tv | DeviceType::Stb | DeviceType::CastVideo => &TV,
+        DeviceType::GameConsole => &GAME_CONSOLE,
+        DeviceType::Automobile => &CAR,
+        DeviceType::Unknown => &UNKNOWN,
+    }
+}
+
+/// Icon of the kind of thing playback started from
+pub(crate) fn context_icon(context: Type) -> &'static Icon {
+    match context {
+        Type::Playlist => &PLAYLIST,
+        Type::Album | Type::Track => &ALBUM,
+        Type::Artist | Type::User => &ARTIST,
+        Type::Show | Type::Episode | Type::Collectionyourepisodes => &SHOW,
+        // Liked songs
+        Type::Collection => &HEART,
+    }
+}
+
+/// 8x8 bitmap, one byte per row with the leftmost pixel in the highest bit
+#[derive(Debug)]
+pub(crate) struct Icon([u8; 8]);
+
+impl Icon {
+    pub(crate) const SIZE: u32 = 8;
+
+    pub(crate) fn draw<D: DrawTarget<Color = Rgb565>>(
+        &self,
+        target: &mut D,
+        top_left: Point,
+        color: Rgb565,
+    ) where
+        D::Error: Debug,
+    {
+        let pixels = self.0.iter().enumerate().flat_map(|(y, row)| {
+            (0..Self::SIZE)
+                .filter(move |x| row & (0x80 >> x) != 0)
+                .map(move |x| Pixel(top_left + Point::new(x as i32, y as i32), color))
+        });
+        target.draw_iter(pixels).unwrap();
+    }
+}
+
+pub(crate) const SHUFFLE: Icon = Icon([
+    0b0000_0010,
+    0b1100_1111,
+    0b0010_1010,
+    0b0001_0000,
+    0b0010_1010,
+    0b1100_1111,
+    0b0000_0010,
+    0b0000_0000,
+]);
+
+pub(crate) const REPEAT: Icon = Icon([
+    0b0010_0000,
+    0b0111_1110,
+    0b0010_0001,
+    0b1000_0001,
+    0b1000_0001,
+    0b1000_0100,
+    0b0111_1110,
+    0b0000_0100,
+]);
+
+/// Repeat with a 1 inside
+pub(crate) const REPEAT_ONE: Icon = Icon([
+    0b0010_0000,
+    0b0111_1110,
+    0b0010_1001,
+    0b1001_1001,
+    0b1000_1001,
+    0b1000_1100,
+    0b0111_1110,
+    0b0000_0100,
+]);
+
+const COMPUTER: Icon = Icon([
+    0b0000_0000,
+    0b0111_1110,
+    0b0100_0010,
+    0b0100_0010,
+    0b0100_0010,
+    0b0111_1110,
+    0b1111_1111,
+    0b0000_0000,
+]);
+
+const TABLET: Icon = Icon([
+    0b0111_1110,
+    0b0100_0010,
+    0b0100_0010,
+    0b0100_0010,
+    0b0100_0010,
+    0b0100_0010,
+    0b0111_1110,
+    0b0000_0000,
+]);
+
+const SMARTPHONE: Icon = Icon([
+    0b0011_1100,
+    0b0010_0100,
+    0b0010_0100,
+    0b0010_0100,
+    0b0010_0100,
+    0b0011_1100,
+    0b0011_1100,
+    0b0000_0000,
+]);
+
+/// Also used for receivers and anything else only playing audio
+const SPEAKER: Icon = Icon([
+    0b0111_1110,
+    0b0101_1010,
+    0b0100_0010,
+    0b0101_1010,
+    0b0110_0110,
+    0b0101_1010,
+    0b0111_1110,
+    0b0000_0000,
+]);
+
+/// Also used for set top boxes and video casting
+const TV: Icon = Icon([
+    0b1111_1111,
+    0b1000_0001,
+    0b1000_0001,
+    0b1000_0001,
+    0b1111_1111,
+    0b0001_1000,
+    0b0111_1110,
+    0b0000_0000,
+]);
+
+const CAR: Icon = Icon([
+    0b0000_0000,
+    0b0011_1100,
+    0b0100_0010,
+    0b1111_1111,
+    0b1011_1101,
+    0b1111_1111,
+    0b0110_0110,
+    0b0000_0000,
+]);
+
+const GAME_CONSOLE: Icon = Icon([
+    0b0000_0000,
+    0b0111_1110,
+    0b1010_0011,
+    0b1110_1001,
+    0b1010_0011,
+    0b0111_1110,
+    0b0000_0000,
+    0b0000_0000,
+]);
+
+/// Question mark
+const UNKNOWN: Icon = Icon([
+    0b0011_1100,
+    0b0100_0010,
+    0b0000_0010,
+    0b0000_1100,
+    0b0001_0000,
+    0b0000_0000,
+    0b0001_0000,
+    0b0000_0000,
+]);
+
+const PLAYLIST: Icon = Icon([
+    0b1111_1000,
+    0b0000_0000,
+    0b1111_1000,
+    0b0000_0010,
+    0b1111_0011,
+    0b0000_0010,
+    0b0000_1110,
+    0b0000_1110,
+]);
+
+/// Also used for single tracks
+const ALBUM: Icon = Icon([
+    0b0011_1100,
+    0b0100_0010,
+    0b1000_0001,
+    0b1001_1001,
+    0b1001_1001,
+    0b1000_0001,
+    0b0100_0010,
+    0b0011_1100,
+]);
+
+/// Also used for user profiles
+const ARTIST: Icon = Icon([
+    0b0001_1000,
+    0b0011_1100,
+    0b0011_1100,
+    0b0001_1000,
+    0b0000_0000,
+    0b0111_1110,
+    0b1111_1111,
+    0b1111_1111,
+]);
+
+/// Microphone for podcasts and their episodes
+const SHOW: Icon = Icon([
+    0b0001_1000,
+    0b0011_1100,
+    0b0011_1100,
+    0b0011_1100,
+    0b0101_1010,
+    0b0010_0100,
+    0b0001_1000,
+    0b0011_1100,
+]);
+
+const HEART: Icon = Icon([
+    0b0110_0110,
+    0b1111_1111,
+    0b1111_1111,
+    0b1111_1111,
+    0b0111_1110,
+    0b0011_1100,
+    0b0001_1000,
+    0b0000_0000,
+]);
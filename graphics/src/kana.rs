@@ -0,0 +1,128 @@
+//! Folds Japanese text into the characters the JIS X 0201 fonts can draw
+
+/// Half-width forms of U+30A1 (ァ) to U+30FA (ヺ), hiragana use the same table from U+3041 (ぁ)
+///
+/// Voiced and semi-voiced kana are split into their base and a separate ﾞ or ﾟ, kana without a
+/// half-width form use the closest one.
+const HALF_KATAKANA: [&str; 90] = [
+    // ァ ア ィ イ ゥ ウ ェ エ ォ オ
+    "ｧ", "ｱ", "ｨ", "ｲ", "ｩ", "ｳ", "ｪ", "ｴ", "ｫ", "ｵ",
+    // カ ガ キ ギ ク グ ケ ゲ コ ゴ
+    "ｶ", "ｶﾞ", "ｷ", "ｷﾞ", "ｸ", "ｸﾞ", "ｹ", "ｹﾞ", "ｺ", "ｺﾞ",
+    // サ ザ シ ジ ス ズ セ ゼ ソ ゾ
+    "ｻ", "ｻﾞ", "ｼ", "ｼﾞ", "ｽ", "ｽﾞ", "ｾ", "ｾﾞ", "ｿ", "ｿﾞ",
+    // タ ダ チ ヂ ッ ツ ヅ テ デ ト
+    "ﾀ", "ﾀﾞ", "ﾁ", "ﾁﾞ", "ｯ", "ﾂ", "ﾂﾞ", "ﾃ", "ﾃﾞ", "ﾄ",
+    // ド ナ ニ ヌ ネ ノ ハ バ パ ヒ
+    "ﾄﾞ", "ﾅ", "ﾆ", "ﾇ", "ﾈ", "ﾉ", "ﾊ", "ﾊﾞ", "ﾊﾟ", "ﾋ",
+    // ビ ピ フ ブ プ ヘ ベ ペ ホ ボ
+    "ﾋﾞ", "ﾋﾟ", "ﾌ", "ﾌﾞ", "ﾌﾟ", "ﾍ", "ﾍﾞ", "ﾍﾟ", "ﾎ", "ﾎﾞ",
+    // ポ マ ミ ム メ モ ャ ヤ ュ ユ
+    "ﾎﾟ", "ﾏ", "ﾐ", "ﾑ", "ﾒ", "ﾓ", "ｬ", "ﾔ", "ｭ", "ﾕ",
+    // ョ ヨ ラ リ ル レ ロ ヮ ワ ヰ
+    "ｮ", "ﾖ", "ﾗ", "ﾘ", "ﾙ", "ﾚ", "ﾛ", "ﾜ", "ﾜ", "ｲ",
+    // ヱ ヲ ン ヴ ヵ ヶ ヷ ヸ ヹ ヺ
+    "ｴ", "ｦ", "ﾝ", "ｳﾞ", "ｶ", "ｹ", "ﾜﾞ", "ｲﾞ", "ｴﾞ", "ｦﾞ",
+];
+
+/// Converts kana to half-width katakana and full-width ASCII and punctuation to their narrow forms
+///
+/// Anything else is kept as is, like the full-width signs such as ￡ whose narrow forms aren't part
+/// of JIS X 0201.
+pub(crate) fn to_jis_x0201(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        let folded = match c {
+            // Fast path, most titles are mostly ASCII
+            c if c.is_ascii() => None,
+            '\u{30a1}'..='\u{30fa}' => Some(HALF_KATAKANA[c as usize - 0x30a1]),
+            '\u{3041}'..='\u{3096}' => Some(HALF_KATAKANA[c as usize - 0x3041]),
+            // Combining and spacing (semi-)voiced sound marks
+            '\u{3099}' | '\u{309b}' => Some("ﾞ"),
+            '\u{309a}' | '\u{309c}' => Some("ﾟ"),
+            'ー' => Some("ｰ"),
+            '。' => Some("｡"),
+            '、' => Some("､"),
+            '・' => Some("･"),
+            '「' | '『' => Some("｢"),
+            '」' | '』' => Some("｣"),
+            '【' | '〔' => Some("["),
+            '】' | '〕' => Some("]"),
+            '〈' | '《' => Some("<"),
+            '〉' | '》' => Some(">"),
+            '〜' => Some("~"),
+            '\u{3000}' => Some(" "),
+            _ => None,
+        };
+
+        match folded {
+            Some(folded) => result.push_str(folded),
+            // Full-width ASCII is offset by a constant
+            None if ('\u{ff01}'..='\u{ff5e}').contains(&c) => {
+                result.push(char::from_u32(c as u32 - 0xfee0).unwrap())
+            }
+            None => result.push(c),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::mono_font::jis_x0201::FONT_6X13;
+
+    use super::*;
+
+    /// Missing characters map to the same glyph as `?`
+    fn in_font(c: char) -> bool {
+        c == '?' || FONT_6X13.glyph_mapping.index(c) != FONT_6X13.glyph_mapping.index('?')
+    }
+
+    fn assert_drawable(from: impl IntoIterator<Item = char>) {
+        for c in from {
+            let folded = to_jis_x0201(&c.to_string());
+            assert!(
+                folded.chars().all(in_font),
+                "U+{:04X} {c} folds to {folded}, which the font can't draw",
+                c as u32
+            );
+        }
+    }
+
+    #[test]
+    fn every_hiragana_is_drawable() {
+        assert_drawable('\u{3041}'..='\u{3096}');
+    }
+
+    #[test]
+    fn every_katakana_is_drawable() {
+        assert_drawable('\u{30a1}'..='\u{30fa}');
+    }
+
+    #[test]
+    fn marks_and_punctuation_are_drawable() {
+        assert_drawable([
+            '\u{3099}', '\u{309a}', '\u{309b}', '\u{309c}', 'ー', '\u{3000}',
+        ]);
+        assert_drawable("。、・「」『』【】〔〕〈〉《》〜".chars());
+    }
+
+    #[test]
+    fn full_width_ascii_is_drawable() {
+        assert_drawable('\u{ff01}'..='\u{ff5e}');
+    }
+
+    #[test]
+    fn voiced_kana_are_split() {
+        assert_eq!(to_jis_x0201("ガ"), "ｶﾞ");
+        assert_eq!(to_jis_x0201("ぱ"), "ﾊﾟ");
+        assert_eq!(to_jis_x0201("ヴ"), "ｳﾞ");
+    }
+
+    #[test]
+    fn other_text_is_kept() {
+        assert_eq!(to_jis_x0201("Hello 漢字 ￡"), "Hello 漢字 ￡");
+    }
+}
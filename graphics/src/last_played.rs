@@ -0,0 +1,89 @@
+use embedded_graphics::{
+    mono_font::MonoTextStyle,
+    prelude::*,
+    primitives::Rectangle,
+    text::{Baseline, Text},
+};
+
+use crate::{progress::clear_ring, Backdrop, Framebuffer, Layout, Palette, PanelFormat, Theme};
+
+/// "Last played" in [`Layout::status`] and how long ago below the track text, shown while nothing
+/// is playing
+///
+/// The cover, title and artists go where they would for a playing track, drawn with
+/// [`crate::draw_album_cover`] and [`crate::draw_current_name_and_artist`]. Without `secs_ago`,
+/// e.g. before the clock is set, the rows under the text are left empty.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_last_played(
+    display: &mut Framebuffer,
+    layout: &Layout,
+    theme: &Theme,
+    secs_ago: Option<u64>,
+    palette: Palette,
+    backdrop: Option<&Backdrop>,
+    format: PanelFormat,
+) {
+    let font = theme.text_size.time_font();
+    let style = MonoTextStyle::new(font, palette.text);
+
+    let area = layout.status_area();
+    let status = layout.status;
+    let mut layer = display.layer(area, palette.background, backdrop, format);
+    Text::with_baseline(
+        "Last played",
+        Point::new(
+            status.top_left.x,
+            status.top_left.y
+                + (status
+                    .size
+                    .height
+                    .saturating_sub(font.character_size.height)
+                    / 2) as i32,
+        ),
+        style,
+        Baseline::Top,
+    )
+    .draw(&mut layer)
+    .unwrap();
+    layer.finish();
+
+    // Lined up with the artists, where the progress bar would be
+    let progress = layout.progress_area();
+    let area = Rectangle::with_corners(
+        progress.top_left,
+        layout
+            .time_area()
+            .bottom_right()
+            .unwrap_or(progress.top_left),
+    );
+    let mut layer = display.layer(area, palette.background, backdrop, format);
+    if let Some(secs) = secs_ago {
+        let ago = ago(secs);
+        let played = format!("played {ago}");
+        // Only the sideways column is too narrow for the whole thing
+        let fits = played.len() as u32 * font.character_size.width <= layout.artists.size.width;
+        Text::with_baseline(
+            if fits { &played } else { &ago },
+            Point::new(layout.artists.top_left.x, layout.progress_bar.top_left.y),
+            style,
+            Baseline::Top,
+        )
+        .draw(&mut layer)
+        .unwrap();
+    }
+    layer.finish();
+    clear_ring(display, layout, palette, backdrop, format);
+}
+
+/// Like `12 min ago`, only the largest unit is kept
+fn ago(secs: u64) -> String {
+    let (minutes, hours, days) = (secs / 60, secs / (60 * 60), secs / (24 * 60 * 60));
+
+    match (minutes, hours, days) {
+        (0, _, _) => "just now".to_string(),
+        (minutes, 0, _) => format!("{minutes} min ago"),
+        (_, hours, 0) => format!("{hours} h ago"),
+        (_, _, 1) => "1 day ago".to_string(),
+        (_, _, days) => format!("{days} days ago"),
+    }
+}
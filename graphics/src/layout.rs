@@ -0,0 +1,453 @@
+use embedded_graphics::{prelude::*, primitives::Rectangle};
+
+use crate::{ProgressStyle, Theme};
+
+/// How the cover and the text are arranged
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Orientation {
+    /// Cover above the text
+    #[default]
+    Portrait,
+    /// Cover on the left, text on the right
+    Landscape,
+}
+
+/// Where everything is drawn, computed from the size of the screen and the fonts and spacing of the [`Theme`]
+///
+/// In portrait the cover takes whatever space the status row, text, progress bar and times leave,
+/// anything left over on tall screens is split above and below everything. In landscape the cover takes up to half
+/// the width and the rest is centered vertically next to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    pub orientation: Orientation,
+    pub screen: Rectangle,
+    /// Shuffle, repeat and device icons, above the cover in portrait and the text in landscape
+    pub status: Rectangle,
+    /// Square the album cover is drawn in, covers have to be resized to its width
+    pub cover: Rectangle,
+    /// Border around the cover the [`ProgressStyle::Ring`] is drawn in, the cover is made smaller to
+    /// leave room for it
+    pub ring: Option<Rectangle>,
+    /// Visible part of the title, the artists are right below
+    pub title: Rectangle,
+    pub artists: Rectangle,
+    /// Row the progress bar is centered in
+    pub progress_bar: Rectangle,
+    /// Elapsed and remaining time right under the bar, lined up with its ends
+    pub time: Rectangle,
+    status_area: Rectangle,
+    cover_area: Rectangle,
+    text_area: Rectangle,
+    progress_area: Rectangle,
+    time_area: Rectangle,
+}
+
+impl Layout {
+    /// Layout for a display with this `bounding_box`
+    pub fn new(screen: Rectangle, orientation: Orientation, theme: &Theme) -> Self {
+        let metrics = Metrics {
+            status_height: theme.text_size.time_font().character_size.height,
+            title_height: theme.text_size.title_fonts().height,
+            artists_height: theme.text_size.artist_fonts().height,
+            cover_margin: theme.spacing.cover_margin,
+            text_inset: theme.spacing.text_inset,
+            progress_height: theme.spacing.progress_height,
+            time_height: theme.text_size.time_font().character_size.height,
+            padding: theme.spacing.padding,
+        };
+
+        let mut layout = match orientation {
+            Orientation::Portrait => Self::portrait(screen, metrics),
+            Orientation::Landscape => Self::landscape(screen, metrics),
+        };
+
+        if theme.progress_style == ProgressStyle::Ring {
+            let thickness = theme.spacing.thickness;
+            let ring = layout.cover;
+            layout.ring = Some(ring);
+            layout.cover = Rectangle::new(
+                ring.top_left + Point::new(thickness as i32, thickness as i32),
+                Size::new(
+                    ring.size.width.saturating_sub(thickness * 2),
+                    ring.size.height.saturating_sub(thickness * 2),
+                ),
+            );
+        }
+
+        layout
+    }
+
+    fn portrait(screen: Rectangle, metrics: Metrics) -> Self {
+        let Metrics {
+            status_height,
+            title_height,
+            artists_height,
+            cover_margin,
+            text_inset,
+            progress_height,
+            time_height,
+            padding,
+        } = metrics;
+        let size = screen.size;
+        let below_cover = title_height + artists_height + progress_height + time_height;
+
+        let side = size
+            .width
+            .saturating_sub(cover_margin * 2)
+            .min(size.height.saturating_sub(status_height + below_cover));
+        let extra = size
+            .height
+            .saturating_sub(status_height + side + below_cover);
+
+        let text_width = size.width.saturating_sub(text_inset * 2);
+        let status = Rectangle::new(
+            screen.top_left + Point::new(text_inset as i32, (extra / 2) as i32),
+            Size::new(text_width, status_height),
+        );
+        let cover = Rectangle::new(
+            Point::new(
+                screen.top_left.x + ((size.width - side) / 2) as i32,
+                bottom(&status),
+            ),
+            Size::new(side, side),
+        );
+        let title = Rectangle::new(
+            Point::new(screen.top_left.x + text_inset as i32, bottom(&cover)),
+            Size::new(text_width, title_height),
+        );
+        let artists = Rectangle::new(
+            Point::new(title.top_left.x, bottom(&title)),
+            Size::new(text_width, artists_height),
+        );
+        let progress_bar = Rectangle::new(
+            Point::new(screen.top_left.x, bottom(&artists)),
+            Size::new(size.width, progress_height),
+        );
+        let time = time_row(&progress_bar, time_height, padding);
+
+        Self {
+            orientation: Orientation::Portrait,
+            screen,
+            status,
+            cover,
+            ring: None,
+            title,
+            artists,
+            progress_bar,
+            time,
+            // Full width rows, everything up from the status row and down from the times belongs to them
+            status_area: rows(&screen, screen.top_left.y, bottom(&status)),
+            cover_area: rows(&screen, bottom(&status), bottom(&cover)),
+            text_area: rows(&screen, title.top_left.y, bottom(&artists)),
+            progress_area: rows(&screen, progress_bar.top_left.y, bottom(&progress_bar)),
+            time_area: rows(&screen, time.top_left.y, bottom(&screen)),
+        }
+    }
+
+    fn landscape(screen: Rectangle, metrics: Metrics) -> Self {
+        let Metrics {
+            status_height,
+            title_height,
+            artists_height,
+            cover_margin,
+            text_inset,
+            progress_height,
+            time_height,
+            padding,
+        } = metrics;
+        let size = screen.size;
+        let column_height =
+            status_height + title_height + artists_height + progress_height + time_height;
+
+        let side = size
+            .height
+            .saturating_sub(cover_margin * 2)
+            .min((size.width / 2).saturating_sub(cover_margin));
+        let cover = Rectangle::new(
+            screen.top_left + Point::new(cover_margin as i32, ((size.height - side) / 2) as i32),
+            Size::new(side, side),
+        );
+
+        // Everything right of the cover, including the margin after it
+        let column_left = right(&cover) + cover_margin as i32;
+        let column = Rectangle::new(
+            Point::new(column_left, screen.top_left.y),
+            Size::new((right(&screen) - column_left).max(0) as u32, size.height),
+        );
+        let column_top = column.top_left.y + (size.height.saturating_sub(column_height) / 2) as i32;
+
+        let text_width = column.size.width.saturating_sub(text_inset * 2);
+        let status = Rectangle::new(
+            Point::new(column_left + text_inset as i32, column_top),
+            Size::new(text_width, status_height),
+        );
+        let title = Rectangle::new(
+            Point::new(status.top_left.x, bottom(&status)),
+            Size::new(text_width, title_height),
+        );
+        let artists = Rectangle::new(
+            Point::new(title.top_left.x, bottom(&title)),
+            Size::new(text_width, artists_height),
+        );
+        let progress_bar = Rectangle::new(
+            Point::new(column_left, bottom(&artists)),
+            Size::new(column.size.width, progress_height),
+        );
+        let time = time_row(&progress_bar, time_height, padding);
+
+        Self {
+            orientation: Orientation::Landscape,
+            screen,
+            status,
+            cover,
+            ring: None,
+            title,
+            artists,
+            progress_bar,
+            time,
+            // Cover owns the left side, the text and progress bar split the column next to it
+            cover_area: Rectangle::new(
+                screen.top_left,
+                Size::new((column_left - screen.top_left.x) as u32, size.height),
+            ),
+            status_area: rows(&column, column.top_left.y, bottom(&status)),
+            text_area: rows(&column, bottom(&status), bottom(&artists)),
+            progress_area: rows(&column, progress_bar.top_left.y, bottom(&progress_bar)),
+            time_area: rows(&column, time.top_left.y, bottom(&column)),
+        }
+    }
+
+    /// Everything [`crate::draw_status`] draws over
+    pub(crate) fn status_area(&self) -> Rectangle {
+        self.status_area
+    }
+
+    /// Everything [`crate::draw_album_cover`] draws over
+    pub(crate) fn cover_area(&self) -> Rectangle {
+        self.cover_area
+    }
+
+    /// [`Layout::cover_area`] split around the ring, drawing over these parts leaves the ring alone
+    pub(crate) fn cover_area_parts(&self) -> Vec<Rectangle> {
+        let Some(ring) = self.ring else {
+            return vec![self.cover_area];
+        };
+
+        let mut parts = around(&self.cover_area, &ring).to_vec();
+        parts.push(self.cover);
+        parts
+    }
+
+    /// The ring without the cover inside it, nothing if there is no ring
+    pub(crate) fn ring_parts(&self) -> Vec<Rectangle> {
+        self.ring
+            .map(|ring| around(&ring, &self.cover).to_vec())
+            .unwrap_or_default()
+    }
+
+    /// Where [`crate::LyricsView`] draws, inside the ring if there is one
+    pub(crate) fn lyrics_area(&self) -> Rectangle {
+        match self.ring {
+            Some(_) => self.cover,
+            None => self.cover_area,
+        }
+    }
+
+    /// Everything [`crate::draw_current_name_and_artist`] draws over
+    pub(crate) fn text_area(&self) -> Rectangle {
+        self.text_area
+    }
+
+    /// Everything [`crate::draw_current_progress`] draws over
+    pub(crate) fn progress_area(&self) -> Rectangle {
+        self.progress_area
+    }
+
+    /// Everything the [`crate::TimeLabels`] may draw over
+    pub(crate) fn time_area(&self) -> Rectangle {
+        self.time_area
+    }
+}
+
+/// Heights and spacing the layout is computed from
+#[derive(Debug, Clone, Copy)]
+struct Metrics {
+    status_height: u32,
+    title_height: u32,
+    artists_height: u32,
+    cover_margin: u32,
+    text_inset: u32,
+    progress_height: u32,
+    time_height: u32,
+    /// Space on both ends of the progress bar
+    padding: u32,
+}
+
+/// Row of `height` right below `progress_bar`, without the padding on its ends
+fn time_row(progress_bar: &Rectangle, height: u32, padding: u32) -> Rectangle {
+    Rectangle::new(
+        Point::new(
+            progress_bar.top_left.x + padding as i32,
+            bottom(progress_bar),
+        ),
+        Size::new(progress_bar.size.width.saturating_sub(padding * 2), height),
+    )
+}
+
+/// Rows from `top` to `bottom` as wide as `area`
+fn rows(area: &Rectangle, top: i32, bottom: i32) -> Rectangle {
+    Rectangle::new(
+        Point::new(area.top_left.x, top),
+        Size::new(area.size.width, (bottom - top).max(0) as u32),
+    )
+}
+
+/// Parts of `outer` above, below, left and right of `inner`, which must be inside it
+fn around(outer: &Rectangle, inner: &Rectangle) -> [Rectangle; 4] {
+    [
+        rows(outer, outer.top_left.y, inner.top_left.y),
+        rows(outer, bottom(inner), bottom(outer)),
+        Rectangle::new(
+            Point::new(outer.top_left.x, inner.top_left.y),
+            Size::new(
+                (inner.top_left.x - outer.top_left.x).max(0) as u32,
+                inner.size.height,
+            ),
+        ),
+        Rectangle::new(
+            Point::new(right(inner), inner.top_left.y),
+            Size::new(
+                (right(outer) - right(inner)).max(0) as u32,
+                inner.size.height,
+            ),
+        ),
+    ]
+}
+
+/// First column right of `rect`
+fn right(rect: &Rectangle) -> i32 {
+    rect.top_left.x + rect.size.width as i32
+}
+
+/// First row below `rect`
+fn bottom(rect: &Rectangle) -> i32 {
+    rect.top_left.y + rect.size.height as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{Context, Playing};
+    use embedded_graphics::pixelcolor::Rgb565;
+
+    use super::*;
+    use crate::{
+        draw_album_cover, draw_current_name_and_artist, draw_current_progress, draw_status,
+        fixtures, placeholder_cover, Artwork, Dither, Framebuffer, MarqueeStyle, PanelFormat,
+        TimeLabels, TrackText,
+    };
+
+    /// Panels the layout has to work on, the same ones the simulator saves with `SNAPSHOTS`
+    const SIZES: [(Size, Orientation); 7] = [
+        (Size::new(128, 128), Orientation::Portrait),
+        (Size::new(128, 160), Orientation::Portrait),
+        (Size::new(240, 240), Orientation::Portrait),
+        (Size::new(240, 320), Orientation::Portrait),
+        (Size::new(320, 480), Orientation::Portrait),
+        (Size::new(160, 128), Orientation::Landscape),
+        (Size::new(320, 240), Orientation::Landscape),
+    ];
+
+    /// Sample track drawn like it would be while playing
+    fn draw_sample(size: Size, orientation: Orientation, theme: &Theme) -> Framebuffer {
+        let playing: Playing =
+            serde_json::from_str(include_str!("../fixtures/snapshot.json")).unwrap();
+        let format = PanelFormat::default();
+
+        let mut display = Framebuffer::new(size, Rgb565::BLACK);
+        let layout = Layout::new(display.bounding_box(), orientation, theme);
+        let cover = placeholder_cover(
+            &playing.playing.name,
+            playing.context.as_ref().and_then(Context::album_name),
+            layout.cover.size.width,
+        );
+        let artwork = Artwork::new(&cover, Dither::default());
+        let palette = theme.track_palette(Some(artwork.palette));
+        let text = TrackText::new(&playing, MarqueeStyle::default(), &layout, theme);
+        let (progress, duration) = (playing.progress_secs, playing.playing.duration);
+
+        draw_status(
+            &mut display,
+            &layout,
+            theme,
+            &playing,
+            true,
+            palette,
+            None,
+            format,
+        );
+        draw_album_cover(
+            &mut display,
+            &layout,
+            Some(&artwork.cover),
+            palette,
+            None,
+            format,
+        );
+        draw_current_name_and_artist(&mut display, &layout, &text, palette, None, format);
+        draw_current_progress(
+            &mut display,
+            &layout,
+            theme,
+            progress,
+            duration,
+            palette,
+            None,
+            format,
+        );
+        TimeLabels::new().draw(
+            &mut display,
+            &layout,
+            theme,
+            progress,
+            duration,
+            palette,
+            None,
+            format,
+        );
+
+        display
+    }
+
+    #[test]
+    fn every_size_matches_references() {
+        for (size, orientation) in SIZES {
+            let display = draw_sample(size, orientation, &Theme::default());
+            fixtures::assert_matches(
+                &format!("layout/{}x{}", size.width, size.height),
+                &fixtures::screenshot(&display),
+            );
+        }
+    }
+
+    #[test]
+    fn everything_is_on_screen() {
+        for (size, orientation) in SIZES {
+            let screen = Rectangle::new(Point::zero(), size);
+            let layout = Layout::new(screen, orientation, &Theme::default());
+
+            for area in [
+                layout.status_area(),
+                layout.cover_area(),
+                layout.text_area(),
+                layout.progress_area(),
+                layout.time_area(),
+            ] {
+                assert_eq!(screen.intersection(&area), area, "{size:?}");
+            }
+            assert!(layout.cover_area().contains(layout.cover.top_left));
+            assert!(layout
+                .cover_area()
+                .contains(layout.cover.bottom_right().unwrap()));
+        }
+    }
+}
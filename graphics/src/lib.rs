@@ -1,43 +1,94 @@
-use std::{fmt::Debug, sync::OnceLock};
+mod backdrop;
+mod counting;
+mod dither;
+#[cfg(test)]
+mod fixtures;
+mod font;
+mod framebuffer;
+mod icon;
+mod kana;
+mod last_played;
+mod layout;
+mod lyrics;
+mod marquee;
+mod palette;
+mod panel;
+mod placeholder;
+mod progress;
+mod status;
+mod theme;
+mod time;
+mod top_tracks;
+mod transition;
+mod translit;
+mod volume;
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use common::Playing;
-use embedded_canvas::{Canvas, CanvasAt};
+use common::{Context, Playing, SimpleTrack};
 use embedded_graphics::{
     geometry::{Point, Size},
     image::{Image, ImageRawBE},
-    mono_font::{
-        jis_x0201::{FONT_6X13, FONT_7X14},
-        MonoTextStyleBuilder,
-    },
-    pixelcolor::Rgb565,
+    mono_font::MonoTextStyleBuilder,
+    pixelcolor::{raw::RawU16, Rgb565},
     prelude::*,
-    primitives::{Circle, Primitive, PrimitiveStyle, Rectangle},
-    text::Text,
+    primitives::{PrimitiveStyle, Rectangle},
     Drawable,
 };
 use embedded_layout::{
     align::{horizontal, vertical, Align},
     layout::linear::LinearLayout,
     object_chain::Chain,
-    View,
 };
 use embedded_text::{style::TextBoxStyleBuilder, TextBox};
 use image::{ImageBuffer, Rgb};
-use unicode_segmentation::UnicodeSegmentation;
 
-static KATAKANA_HALF: OnceLock<Vec<Box<str>>> = OnceLock::new();
-static KATAKANA_FULL: OnceLock<Vec<Box<str>>> = OnceLock::new();
-pub const SCREEN_WIDTH: u32 = 128;
-pub const SCREEN_HEIGHT: u32 = 160;
-pub const IMAGE_WIDTH: u32 = 114;
+use icon::{context_icon, Icon};
+use palette::mix_rgb565;
 
-pub fn rgb8_to_rgb565(image: &ImageBuffer<Rgb<u8>, Vec<u8>>) -> Vec<u8> {
+pub use backdrop::Backdrop;
+pub use counting::{CountingTarget, FrameStats};
+pub use dither::Dither;
+pub use font::{
+    BitmapFont, Font, FontSet, ARTIST_FONTS, LARGE_ARTIST_FONTS, LARGE_TITLE_FONTS, TITLE_FONTS,
+};
+pub use framebuffer::{Framebuffer, Layer};
+pub use last_played::draw_last_played;
+pub use layout::{Layout, Orientation};
+pub use lyrics::LyricsView;
+pub use marquee::{Marquee, MarqueeMode, MarqueeStyle};
+pub use palette::Palette;
+pub use panel::{ByteOrder, ColorOrder, PanelFormat};
+pub use placeholder::placeholder_cover;
+pub use progress::{ProgressIndicator, ProgressStyle};
+pub use status::draw_status;
+pub use theme::{Spacing, TextSize, Theme};
+pub use time::TimeLabels;
+pub use top_tracks::{draw_top_track, TimeRange, TopTracks};
+pub use transition::{CoverTransition, Transition};
+pub use volume::VolumeOverlay;
+
+/// How far the cover is faded into the background while paused
+const PAUSED_FADE: f32 = 0.6;
+/// Shortest time the artists or the "Playing from" line stay up before swapping
+const CONTEXT_SWAP_INTERVAL: Duration = Duration::from_secs(5);
+/// Space between the context icon and its name
+const CONTEXT_ICON_GAP: u32 = 3;
+
+/// Converts to big endian RGB565 bytes, in logical colors like everything else that is drawn
+///
+/// Covers are drawn far more often than they are created, so this is the only conversion they go
+/// through before being converted for the panel as they are drawn.
+pub fn rgb8_to_rgb565(image: &ImageBuffer<Rgb<u8>, Vec<u8>>, dither: Dither) -> Vec<u8> {
     // Result is 2/3 of the size of the original
     let mut result = vec![0u8; (image.as_raw().len() as f32 * (2. / 3.)).round() as usize];
+    let mut ditherer = dither::Ditherer::new(dither, image.width());
 
-    for (i, Rgb([r, g, b])) in image.pixels().copied().enumerate() {
-        let rgb565 = rgb565::Rgb565::from_rgb888_components(b, g, r);
-        let [first, second] = rgb565.to_bgr565_be();
+    for (i, (x, y, Rgb(pixel))) in image.enumerate_pixels().enumerate() {
+        let [first, second] = ditherer.apply(x, y, *pixel).into_storage().to_be_bytes();
 
         result[i * 2] = first;
         result[(i * 2) + 1] = second;
@@ -46,279 +97,321 @@ pub fn rgb8_to_rgb565(image: &ImageBuffer<Rgb<u8>, Vec<u8>>) -> Vec<u8> {
     result
 }
 
-const CURRENT_TRACK_HEIGHT: u32 = IMAGE_WIDTH + 14 + 13;
-
-pub fn draw_album_cover<D: DrawTargetExt<Color = Rgb565>>(display: &mut D, image: Option<&[u8]>)
-where
-    D::Error: Debug,
-{
-    let display_area = display.bounding_box();
-
-    let image = if let Some(image) = image {
-        ImageRawBE::<Rgb565>::new(image, IMAGE_WIDTH)
-    } else {
-        ImageRawBE::new(&[], IMAGE_WIDTH)
-    };
+/// Album cover ready to be drawn, along with the colors picked from it
+#[derive(Debug, Clone)]
+pub struct Artwork {
+    /// Created by [`rgb8_to_rgb565`]
+    pub cover: Arc<[u8]>,
+    pub palette: Palette,
+    /// Only created with [`Artwork::with_backdrop`]
+    pub backdrop: Option<Backdrop>,
+}
 
-    let chain = Chain::new(Image::new(&image, Point::zero()));
+impl Artwork {
+    /// `image` should already be resized to the width of [`Layout::cover`]
+    pub fn new(image: &ImageBuffer<Rgb<u8>, Vec<u8>>, dither: Dither) -> Self {
+        Self {
+            cover: Arc::from(rgb8_to_rgb565(image, dither)),
+            palette: Palette::from_image(image),
+            backdrop: None,
+        }
+    }
 
-    let mut canvas = Canvas::<Rgb565>::new(Size::new(display_area.size.width, IMAGE_WIDTH));
-    canvas.clear(Rgb565::new(0, 0, 0)).unwrap();
+    /// Also creates a blurred backdrop of `size` from the same `image`, readable with the text
+    /// color `theme` draws this track with
+    pub fn with_backdrop(
+        mut self,
+        image: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+        size: Size,
+        theme: &Theme,
+    ) -> Self {
+        let palette = theme.track_palette(Some(self.palette));
+        self.backdrop = Some(Backdrop::new(image, size, palette.text));
+        self
+    }
+}
 
-    LinearLayout::vertical(chain)
-        .with_alignment(horizontal::Center)
-        .arrange()
-        .align_to(
-            &Rectangle::new(Point::zero(), canvas.size()),
-            horizontal::Center,
-            vertical::Top,
-        )
-        .draw(&mut canvas)
-        .unwrap();
+/// `image` must be created by [`rgb8_to_rgb565`]
+pub fn draw_album_cover(
+    display: &mut Framebuffer,
+    layout: &Layout,
+    image: Option<&[u8]>,
+    palette: Palette,
+    backdrop: Option<&Backdrop>,
+    format: PanelFormat,
+) {
+    draw_cover(display, layout, image, false, palette, backdrop, format);
+}
 
-    // Draw all changes at once
-    let canvas = canvas.place_at(Point::zero());
-    draw_canvas_with_background(canvas, Rgb565::new(0, 0, 0), display);
+/// Same as [`draw_album_cover`], but faded into the background with a pause sign on top
+///
+/// Drawing the cover with [`draw_album_cover`] again clears it.
+pub fn draw_paused_cover(
+    display: &mut Framebuffer,
+    layout: &Layout,
+    image: Option<&[u8]>,
+    palette: Palette,
+    backdrop: Option<&Backdrop>,
+    format: PanelFormat,
+) {
+    draw_cover(display, layout, image, true, palette, backdrop, format);
 }
 
-const TEXT_HEIGHT: u32 = 14 + 13;
-
-/// Sets the shift to 0 when it has no effect (it has made a full wrap)
-pub fn draw_current_name_and_artist<D: DrawTargetExt<Color = Rgb565>>(
-    display: &mut D,
-    playing: &Playing,
-    title_shift: &mut u32,
-    composer_shift: &mut u32,
-) where
-    D::Error: Debug,
-{
-    let Playing { playing, .. } = playing;
-
-    let area = Rectangle::new(
-        Point::new(0, IMAGE_WIDTH as i32),
-        Size::new(display.bounding_box().size.width - 4, TEXT_HEIGHT),
+fn draw_cover(
+    display: &mut Framebuffer,
+    layout: &Layout,
+    image: Option<&[u8]>,
+    paused: bool,
+    palette: Palette,
+    backdrop: Option<&Backdrop>,
+    format: PanelFormat,
+) {
+    let area = layout.cover_area();
+
+    // Only a paused cover has to be copied, it's drawn straight from the artwork otherwise
+    let faded = image.filter(|_| paused).map(|image| {
+        image
+            .chunks_exact(2)
+            .flat_map(|bytes| {
+                let color = Rgb565::from(RawU16::new(u16::from_be_bytes([bytes[0], bytes[1]])));
+                mix_rgb565(color, palette.background, PAUSED_FADE)
+                    .into_storage()
+                    .to_be_bytes()
+            })
+            .collect::<Vec<_>>()
+    });
+    let image = ImageRawBE::<Rgb565>::new(
+        faded.as_deref().or(image).unwrap_or(&[]),
+        layout.cover.size.width,
     );
 
-    let name_text_style = MonoTextStyleBuilder::new()
-        .font(&FONT_7X14)
-        .text_color(Rgb565::new(255, 255, 255))
-        .build();
-    let composer_text_style = MonoTextStyleBuilder::new()
-        .font(&FONT_6X13)
-        .text_color(Rgb565::new(255, 255, 255))
-        .build();
-
-    let name_bounds = Rectangle::new(Point::zero(), Size::new(area.size.width, 14));
-    let composer_bounds = Rectangle::new(Point::zero(), Size::new(area.size.width, 13));
-
-    let mut playing_half = full_katakana_to_half_katakana(&playing.name);
-    let mut composer_half = playing
-        .artists
-        .iter()
-        .map(|artist| full_katakana_to_half_katakana(artist.name.as_str()))
-        .collect::<Vec<_>>()
-        .join(", ");
-
-    let mut name_text = Text::with_baseline(
-        &playing_half,
-        name_bounds.top_left,
-        name_text_style,
-        embedded_graphics::text::Baseline::Top,
-    );
-    let mut composer_text = Text::with_baseline(
-        &composer_half,
-        composer_bounds.top_left,
-        composer_text_style,
-        embedded_graphics::text::Baseline::Top,
-    );
+    let mut layer = display.layer(area, palette.background, backdrop, format);
+    Image::new(&image, layout.cover.top_left)
+        .draw(&mut layer)
+        .unwrap();
 
-    let mut canvas = Canvas::<Rgb565>::new(area.size);
-    let mut name_canvas = Canvas::<Rgb565>::new(name_text.bounding_box().size());
-    name_text.draw(&mut name_canvas).unwrap();
-
-    if name_text.bounding_box().size.width > SCREEN_WIDTH - 4 {
-        playing_half.push_str("  ");
-        name_text = Text::with_baseline(
-            &playing_half,
-            name_bounds.top_left,
-            name_text_style,
-            embedded_graphics::text::Baseline::Top,
-        );
-        name_canvas = Canvas::<Rgb565>::new(name_text.bounding_box().size());
-        name_text.draw(&mut name_canvas).unwrap();
-
-        if *title_shift != 0 {
-            // Name text is overflowing, start wrapping pixels it by shift
-            let mut new_pixels = vec![None::<Rgb565>; name_canvas.pixels.len()];
-            for (og_i, Point { x, y }) in name_canvas.bounding_box().points().enumerate() {
-                let new_x = (x - *title_shift as i32)
-                    .rem_euclid(name_canvas.bounding_box().size.width as i32);
-                let index = point_to_index(
-                    name_text.bounding_box().size,
-                    Point::zero(),
-                    Point::new(new_x, y),
-                )
+    if paused {
+        // Two bars in the middle of the cover
+        let side = layout.cover.size.width;
+        let bar = Size::new((side / 10).max(2), side / 3);
+        let center = layout.cover.center();
+        let gap = (bar.width / 2) as i32;
+        for x in [center.x - gap - bar.width as i32, center.x + gap] {
+            Rectangle::new(Point::new(x, center.y - (bar.height / 2) as i32), bar)
+                .into_styled(PrimitiveStyle::with_fill(palette.text))
+                .draw(&mut layer)
                 .unwrap();
+        }
+    }
 
-                new_pixels[index] = name_canvas.pixels[og_i];
-            }
-            name_canvas.pixels = new_pixels.into_boxed_slice();
+    // The ring around the cover is left alone so the progress drawn there isn't erased
+    layer.finish_parts(&layout.cover_area_parts());
+}
+
+/// Title and artists of the current track, only one of them scrolls at a time
+///
+/// If the track is played from a named playlist, album or the like, the artists take turns with a
+/// "Playing from" line.
+#[derive(Debug, Clone)]
+pub struct TrackText {
+    title: Marquee,
+    artists: Marquee,
+    context: Option<ContextLine>,
+    scrolling_title: bool,
+    /// Context is shown instead of the artists
+    showing_context: bool,
+    /// When the artists or the context were last swapped
+    swapped_at: Instant,
+}
 
-            if *title_shift == name_text.bounding_box().size.width {
-                *title_shift = 0;
-            }
+/// Where the track is played from, drawn after an icon of its type
+#[derive(Debug, Clone)]
+struct ContextLine {
+    icon: &'static Icon,
+    text: Marquee,
+}
+
+impl TrackText {
+    /// Lines are as wide as [`Layout::title`] and [`Layout::artists`], in the fonts of `theme`
+    pub fn new(playing: &Playing, style: MarqueeStyle, layout: &Layout, theme: &Theme) -> Self {
+        Self::from_track(
+            &playing.playing,
+            playing.context.as_ref(),
+            style,
+            layout,
+            theme,
+        )
+    }
+
+    /// Same as [`TrackText::new`] for a track which isn't playing right now
+    pub fn from_track(
+        track: &SimpleTrack,
+        context: Option<&Context>,
+        style: MarqueeStyle,
+        layout: &Layout,
+        theme: &Theme,
+    ) -> Self {
+        let artists = track
+            .artists
+            .iter()
+            .map(|artist| artist.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let context = context.and_then(|context| {
+            Some(ContextLine {
+                icon: context_icon(context._type),
+                text: Marquee::new(
+                    &format!("Playing from: {}", context.name.as_ref()?),
+                    theme.text_size.artist_fonts(),
+                    layout
+                        .artists
+                        .size
+                        .width
+                        .saturating_sub(Icon::SIZE + CONTEXT_ICON_GAP),
+                    style,
+                ),
+            })
+        });
+
+        // Artists only start moving once the title is done
+        Self {
+            title: Marquee::new(
+                &track.name,
+                theme.text_size.title_fonts(),
+                layout.title.size.width,
+                style,
+            ),
+            artists: Marquee::new(
+                &artists,
+                theme.text_size.artist_fonts(),
+                layout.artists.size.width,
+                style,
+            ),
+            context,
+            scrolling_title: true,
+            showing_context: false,
+            swapped_at: Instant::now(),
         }
-    } else {
-        *title_shift = 0;
     }
 
-    name_canvas
-        .crop(&Rectangle::new(
-            Point::zero(),
-            Size::new(SCREEN_WIDTH - 2, 14),
-        ))
-        .unwrap()
-        .place_at(Point::new(2, 0))
-        .draw(&mut canvas)
-        .unwrap();
+    /// Moves whichever line is scrolling, returns `true` if it has to be redrawn
+    pub fn update(&mut self) -> bool {
+        let current = if self.scrolling_title {
+            &mut self.title
+        } else {
+            self.second_line()
+        };
+        let mut moved = current.update();
+        if !current.is_finished() {
+            return moved;
+        }
 
-    let mut composer_canvas = Canvas::<Rgb565>::new(composer_text.bounding_box().size());
-    composer_text.draw(&mut composer_canvas).unwrap();
-
-    if composer_text.bounding_box().size.width > SCREEN_WIDTH - 4 {
-        composer_half.push_str("  ");
-        composer_text = Text::with_baseline(
-            &composer_half,
-            composer_bounds.top_left,
-            composer_text_style,
-            embedded_graphics::text::Baseline::Top,
-        );
-        composer_canvas = Canvas::<Rgb565>::new(composer_text.bounding_box().size());
-        composer_text.draw(&mut composer_canvas).unwrap();
-
-        if *composer_shift != 0 {
-            // Name text is overflowing, start wrapping pixels it by shift
-            let mut new_pixels = vec![None::<Rgb565>; composer_canvas.pixels.len()];
-            for (og_i, Point { x, y }) in composer_canvas.bounding_box().points().enumerate() {
-                let new_x = (x - *composer_shift as i32)
-                    .rem_euclid(composer_canvas.bounding_box().size.width as i32);
-                let index = point_to_index(
-                    composer_text.bounding_box().size,
-                    Point::zero(),
-                    Point::new(new_x, y),
-                )
-                .unwrap();
+        // Artists and context swap once the one shown is done and has been up for long enough
+        if !self.scrolling_title
+            && self.context.is_some()
+            && self.swapped_at.elapsed() >= CONTEXT_SWAP_INTERVAL
+        {
+            self.showing_context = !self.showing_context;
+            self.swapped_at = Instant::now();
+            self.second_line().restart();
+            moved = true;
+        }
+
+        self.scrolling_title = !self.scrolling_title;
+        if self.scrolling_title {
+            self.title.restart();
+        } else {
+            self.second_line().restart();
+        }
 
-                new_pixels[index] = composer_canvas.pixels[og_i];
-            }
-            composer_canvas.pixels = new_pixels.into_boxed_slice();
+        moved
+    }
 
-            if *composer_shift == composer_text.bounding_box().size.width {
-                *composer_shift = 0;
-            }
+    /// Artists or the context, whichever is shown
+    fn second_line(&mut self) -> &mut Marquee {
+        match &mut self.context {
+            Some(context) if self.showing_context => &mut context.text,
+            _ => &mut self.artists,
         }
-    } else {
-        *composer_shift = 0;
     }
+}
 
-    composer_canvas
-        .crop(&Rectangle::new(
-            Point::zero(),
-            Size::new(SCREEN_WIDTH - 2, 13),
-        ))
-        .unwrap()
-        .place_at(Point::new(2, 14))
-        .draw(&mut canvas)
-        .unwrap();
+pub fn draw_current_name_and_artist(
+    display: &mut Framebuffer,
+    layout: &Layout,
+    text: &TrackText,
+    palette: Palette,
+    backdrop: Option<&Backdrop>,
+    format: PanelFormat,
+) {
+    let area = layout.text_area();
+
+    let mut layer = display.layer(area, palette.background, backdrop, format);
+    text.title
+        .draw(&mut layer, layout.title.top_left, palette.text);
+    match &text.context {
+        Some(context) if text.showing_context => {
+            let top_left = layout.artists.top_left;
+            let icon_top = (layout.artists.size.height.saturating_sub(Icon::SIZE) / 2) as i32;
+            context
+                .icon
+                .draw(&mut layer, top_left + Point::new(0, icon_top), palette.text);
+            context.text.draw(
+                &mut layer,
+                top_left + Point::new((Icon::SIZE + CONTEXT_ICON_GAP) as i32, 0),
+                palette.text,
+            );
+        }
+        _ => text
+            .artists
+            .draw(&mut layer, layout.artists.top_left, palette.text),
+    }
 
     // Draw all changes at once
-    let canvas = canvas.place_at(area.top_left);
-    draw_canvas_with_background(canvas, Rgb565::new(0, 0, 0), display);
+    layer.finish();
 }
 
-const PADDING: u32 = 10;
-const CIRCLE_RADIUS: u32 = 4;
-const THICKNESS: u32 = 3;
-
-pub fn draw_current_progress<D: DrawTargetExt<Color = Rgb565>>(
-    display: &mut D,
+/// Drawn in the [`ProgressStyle`] of `theme`, an empty bar while `duration` is still unknown
+#[allow(clippy::too_many_arguments)]
+pub fn draw_current_progress(
+    display: &mut Framebuffer,
+    layout: &Layout,
+    theme: &Theme,
     progress_secs: u32,
     duration: u32,
-) where
-    D::Error: Debug,
-{
-    let display_area = display.bounding_box();
-    let area = Rectangle::new(
-        Point::new(0, CURRENT_TRACK_HEIGHT as i32),
-        Size::new(
-            display_area.size.width,
-            display_area.size.height - CURRENT_TRACK_HEIGHT,
-        ),
-    );
-    let bar_width = area.size.width - PADDING * 2;
-    // Find percentage of bar and add 2 for padding
-    let progress_width = ((bar_width as f32 * ((progress_secs + 1) as f32 / duration as f32))
-        .round() as u32)
-        .clamp(0, bar_width);
-
-    let progress_width = progress_width.saturating_sub(CIRCLE_RADIUS);
-    let remaining_width = bar_width
-        .saturating_sub(progress_width)
-        .saturating_sub(CIRCLE_RADIUS);
-
-    let mut canvas = Canvas::<Rgb565>::new(area.size);
-
-    canvas.clear(Rgb565::new(0, 0, 0)).unwrap();
-    LinearLayout::horizontal(
-        Chain::new(
-            Rectangle::new(
-                Point::zero(),
-                Size::new(progress_width.clamp(1, u32::MAX), THICKNESS),
-            )
-            .into_styled(PrimitiveStyle::with_fill(if progress_width != 0 {
-                rgb888_to_rgb565(0x1d, 0xb9, 0x54)
-            } else {
-                Rgb565::new(0, 0, 0)
-            })),
-        )
-        .append(
-            Circle::new(Point::zero(), CIRCLE_RADIUS * 2 + 1).into_styled(
-                PrimitiveStyle::with_fill(rgb888_to_rgb565(0x1d, 0xb9, 0x54)),
-            ),
-        )
-        .append(
-            Rectangle::new(
-                Point::zero(),
-                Size::new(remaining_width.clamp(1, u32::MAX), THICKNESS),
-            )
-            .into_styled(PrimitiveStyle::with_fill(if remaining_width != 0 {
-                rgb888_to_rgb565(160, 160, 160)
-            } else {
-                Rgb565::new(0, 0, 0)
-            })),
-        ),
-    )
-    .with_alignment(vertical::Center)
-    .arrange()
-    .align_to(&canvas.bounding_box(), horizontal::Center, vertical::Center)
-    .draw(&mut canvas)
-    .unwrap();
-
-    // Draw canvas to display replacing emply pixels with the background color, need this cause canvas slow otherwise
-    let canvas = canvas.place_at(area.top_left);
-    draw_canvas_with_background(canvas, Rgb565::new(0, 0, 0), display);
+    palette: Palette,
+    backdrop: Option<&Backdrop>,
+    format: PanelFormat,
+) {
+    let progress = if duration == 0 {
+        0.
+    } else {
+        (progress_secs as f32 / duration as f32).min(1.)
+    };
+
+    let indicator = theme.progress_style.indicator();
+    let area = indicator.area(layout);
+    let mut layer = if indicator.is_overlay() {
+        // Keep the row of the bar clear
+        display
+            .layer(layout.progress_area(), palette.background, backdrop, format)
+            .finish();
+        display.overlay(area, format)
+    } else {
+        display.layer(area, palette.background, backdrop, format)
+    };
+    indicator.draw(&mut layer, layout, &theme.spacing, progress, palette);
+    layer.finish();
 }
 
-pub fn draw_no_song<D: DrawTargetExt<Color = Rgb565>>(display: &mut D)
-where
-    D::Error: Debug,
-{
+pub fn draw_no_song(display: &mut Framebuffer, theme: &Theme, format: PanelFormat) {
     let display_area = display.bounding_box();
 
     let text_style = MonoTextStyleBuilder::new()
-        .font(&FONT_7X14)
-        .text_color(Rgb565::new(255, 255, 255))
-        .background_color(Rgb565::new(0, 0, 0))
+        .font(theme.text_size.message_font())
+        .text_color(theme.palette.text)
+        .background_color(theme.palette.background)
         .build();
 
     let text_box_style = TextBoxStyleBuilder::new()
@@ -330,85 +423,17 @@ where
 
     let text = TextBox::with_textbox_style("Not Playing", display_area, text_style, text_box_style);
 
-    let mut canvas = Canvas::<Rgb565>::new(display_area.size);
-
+    let mut layer = display.layer(display_area, theme.palette.background, None, format);
     LinearLayout::vertical(Chain::new(text))
         .with_alignment(horizontal::Center)
         .arrange()
         .align_to(&display_area, horizontal::Center, vertical::Top)
-        .draw(&mut canvas)
+        .draw(&mut layer)
         .unwrap();
-
-    let canvas = canvas.place_at(Point::zero());
-    draw_canvas_with_background(canvas, Rgb565::new(0, 0, 0), display);
+    layer.finish();
 }
 
-fn rgb888_to_rgb565(r: u8, g: u8, b: u8) -> Rgb565 {
+pub(crate) fn rgb888_to_rgb565(r: u8, g: u8, b: u8) -> Rgb565 {
     let [r, g, b] = rgb565::Rgb565::from_rgb888_components(r, g, b).to_rgb565_components();
     Rgb565::new(r, g, b)
 }
-
-/// Draw canvas more efficiently than default `draw` impl
-fn draw_canvas_with_background<D: DrawTargetExt<Color = Rgb565>>(
-    canvas: CanvasAt<Rgb565>,
-    background: Rgb565,
-    target: &mut D,
-) where
-    D::Error: Debug,
-{
-    target
-        .fill_contiguous(
-            &canvas.bounding_box(),
-            canvas
-                .bounding_box()
-                .points()
-                .map(|point| canvas.get_pixel(point).unwrap_or(background)),
-        )
-        .unwrap();
-}
-
-fn full_katakana_to_half_katakana(full: &str) -> String {
-    // TODO: optimize by making each katakana its own string so they can be static
-    let katakana_half = KATAKANA_HALF.get_or_init(|| {
-        "｡｢｣､･ｦｧｨｩｪｫｬｭｮｯｰｱｲｳｴｵｶｷｸｹｺｻｼｽｾｿﾀﾁﾂﾃﾄﾅﾆﾇﾈﾉﾊﾋﾌﾍﾎﾏﾐﾑﾒﾓﾔﾕﾖﾗﾘﾙﾚﾛﾜﾝﾞﾟ"
-            .graphemes(true)
-            .map(Box::from)
-            .collect()
-    });
-    let katakana_full = KATAKANA_FULL.get_or_init(|| {
-		"。「」、・ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン゛゜".graphemes(true).map(Box::from).collect()
-	});
-
-    full.graphemes(true)
-        .map(|grapheme| {
-            if grapheme.is_ascii() {
-                // Return early if ascii to avoid searching through katakana
-                return grapheme;
-            }
-
-            if let Some(index) = katakana_full
-                .iter()
-                .enumerate()
-                .find_map(|(i, kana_full)| (&**kana_full == grapheme).then(|| i))
-            {
-                &*katakana_half[index]
-            } else {
-                grapheme
-            }
-        })
-        .fold(String::with_capacity(full.len()), |mut str, grapheme| {
-            str.push_str(&grapheme);
-            str
-        })
-}
-
-fn point_to_index(size: Size, top_left_offset: Point, point: Point) -> Option<usize> {
-    // we must account for the top_left corner of the drawing box
-    if let Ok((x, y)) = <(u32, u32)>::try_from(point - top_left_offset) {
-        if x < size.width && y < size.height {
-            return Some((x + y * size.width) as usize);
-        }
-    }
-
-    None
-}
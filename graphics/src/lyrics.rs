@@ -0,0 +1,189 @@
+use common::Lyrics;
+use embedded_graphics::prelude::*;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{Backdrop, FontSet, Framebuffer, Layer, Layout, Palette, PanelFormat, Theme};
+
+/// Space between two lines of the lyrics, rows of one wrapped line have none
+const LINE_GAP: i32 = 3;
+
+/// Synced lyrics in place of the cover, the line being sung in the text color and the rest dimmed
+///
+/// Lines are wrapped at spaces to the width of the cover area. Remembers which line was current
+/// when last drawn, so nothing is drawn until the next one starts.
+#[derive(Debug, Clone)]
+pub struct LyricsView {
+    lyrics: Lyrics,
+    /// Rows of every line once wrapped
+    rows: Vec<Vec<String>>,
+    drawn: Option<Drawn>,
+}
+
+#[derive(Debug, Clone)]
+struct Drawn {
+    current: Option<usize>,
+    palette: Palette,
+}
+
+impl LyricsView {
+    pub fn new(lyrics: Lyrics, layout: &Layout, theme: &Theme) -> Self {
+        let fonts = theme.text_size.artist_fonts();
+        let width = layout
+            .lyrics_area()
+            .size
+            .width
+            .saturating_sub(theme.spacing.text_inset * 2);
+
+        Self {
+            rows: lyrics
+                .lines
+                .iter()
+                .map(|line| wrap(&line.text, fonts, width))
+                .collect(),
+            lyrics,
+            drawn: None,
+        }
+    }
+
+    /// Draw everything again next time, needed once whatever is behind the lyrics changed
+    pub fn clear(&mut self) {
+        self.drawn = None;
+    }
+
+    /// Draws the lyrics around the line at `progress_ms` if another line started since the last
+    /// draw, returns `true` if anything was drawn
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &mut self,
+        display: &mut Framebuffer,
+        layout: &Layout,
+        theme: &Theme,
+        progress_ms: u32,
+        palette: Palette,
+        backdrop: Option<&Backdrop>,
+        format: PanelFormat,
+    ) -> bool {
+        let current = self.lyrics.line_at(progress_ms);
+        if self
+            .drawn
+            .as_ref()
+            .is_some_and(|drawn| drawn.current == current && drawn.palette == palette)
+        {
+            return false;
+        }
+
+        let area = layout.lyrics_area();
+        let fonts = theme.text_size.artist_fonts();
+        let left = area.top_left.x + theme.spacing.text_inset as i32;
+        let row_height = fonts.height as i32;
+        let draw_rows = |layer: &mut Layer, rows: &[String], top: i32, color| {
+            for (i, row) in rows.iter().enumerate() {
+                let top_left = Point::new(left, area.top_left.y + top + i as i32 * row_height);
+                fonts.draw(row, top_left, color, layer);
+            }
+        };
+
+        let mut layer = display.layer(area, palette.background, backdrop, format);
+        // Current line a third of the way down, the ones before it above
+        let anchor = (area.size.height / 3) as i32;
+        let first = current.unwrap_or(0);
+
+        let mut top = anchor;
+        for (i, rows) in self.rows.iter().enumerate().skip(first) {
+            if top >= area.size.height as i32 {
+                break;
+            }
+            let color = if Some(i) == current {
+                palette.text
+            } else {
+                palette.remaining
+            };
+            draw_rows(&mut layer, rows, top, color);
+            top += rows.len() as i32 * row_height + LINE_GAP;
+        }
+
+        let mut bottom = anchor - LINE_GAP;
+        for rows in self.rows[..first].iter().rev() {
+            if bottom <= 0 {
+                break;
+            }
+            let top = bottom - rows.len() as i32 * row_height;
+            draw_rows(&mut layer, rows, top, palette.remaining);
+            bottom = top - LINE_GAP;
+        }
+
+        layer.finish();
+
+        self.drawn = Some(Drawn { current, palette });
+        true
+    }
+}
+
+/// Splits `text` at spaces into rows no wider than `width`
+///
+/// Words too wide for a row of their own are broken between graphemes, which is how lines of CJK
+/// text without any spaces end up wrapped.
+fn wrap(text: &str, fonts: &FontSet, width: u32) -> Vec<String> {
+    let mut rows = Vec::new();
+    let mut row = String::new();
+
+    for word in text.split_whitespace() {
+        if !row.is_empty() {
+            let longer = format!("{row} {word}");
+            if fonts.width(&longer) <= width {
+                row = longer;
+                continue;
+            }
+            rows.push(std::mem::take(&mut row));
+        }
+
+        for grapheme in word.graphemes(true) {
+            let longer = format!("{row}{grapheme}");
+            if row.is_empty() || fonts.width(&longer) <= width {
+                row = longer;
+            } else {
+                rows.push(std::mem::replace(&mut row, grapheme.to_string()));
+            }
+        }
+    }
+
+    // Breaks between lines keep an empty row
+    rows.push(row);
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextSize;
+
+    #[test]
+    fn wraps_at_spaces() {
+        let fonts = TextSize::Regular.artist_fonts();
+        let width = fonts.width("one two three");
+
+        assert_eq!(
+            wrap("one two three four five", fonts, width),
+            ["one two three", "four five"]
+        );
+    }
+
+    #[test]
+    fn breaks_lines_without_spaces_between_graphemes() {
+        let fonts = TextSize::Regular.artist_fonts();
+        let line = "ありがとうございました";
+        let width = fonts.width(line) / 2;
+
+        let rows = wrap(line, fonts, width);
+        assert!(rows.len() > 1);
+        assert_eq!(rows.concat(), line);
+        assert!(rows.iter().all(|row| fonts.width(row) <= width));
+    }
+
+    #[test]
+    fn keeps_breaks_between_lines() {
+        let fonts = TextSize::Regular.artist_fonts();
+
+        assert_eq!(wrap("", fonts, 100), [""]);
+    }
+}
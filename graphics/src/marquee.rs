@@ -0,0 +1,293 @@
+use std::{
+    fmt::Debug,
+    time::{Duration, Instant},
+};
+
+use embedded_graphics::{
+    pixelcolor::{BinaryColor, Rgb565},
+    prelude::*,
+    primitives::Rectangle,
+};
+
+use crate::FontSet;
+
+/// Space between the end and the start of looping text
+const LOOP_GAP: &str = "  ";
+
+/// How a [`Marquee`] moves text which doesn't fit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MarqueeMode {
+    /// Wraps around until the start is back in place
+    #[default]
+    Loop,
+    /// Scrolls to the end, pauses, then scrolls back
+    PingPong,
+    /// Scrolls to the end, pauses, then jumps back to the start
+    PauseAtEnds,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarqueeStyle {
+    pub mode: MarqueeMode,
+    /// Pixels per second
+    pub speed: f32,
+    /// Time spent still at each end before moving
+    pub pause: Duration,
+}
+
+impl Default for MarqueeStyle {
+    fn default() -> Self {
+        Self {
+            mode: MarqueeMode::Loop,
+            speed: 75.,
+            pause: Duration::from_secs(3),
+        }
+    }
+}
+
+/// One line of text which scrolls when it is wider than the space it is drawn in
+///
+/// The text is rendered once, call [`Marquee::update`] every tick to move it.
+#[derive(Debug, Clone)]
+pub struct Marquee {
+    /// Rendered text, row by row, `true` where it is drawn
+    strip: Box<[bool]>,
+    strip_size: Size,
+    /// Visible width
+    width: u32,
+    style: MarqueeStyle,
+    started_at: Instant,
+    offset: u32,
+    finished: bool,
+}
+
+impl Marquee {
+    pub fn new(text: &str, fonts: &FontSet, width: u32, style: MarqueeStyle) -> Self {
+        let strip = if style.mode == MarqueeMode::Loop && fonts.width(text) > width {
+            fonts.render(&format!("{text}{LOOP_GAP}"), BinaryColor::On)
+        } else {
+            fonts.render(text, BinaryColor::On)
+        };
+
+        Self {
+            strip_size: strip.size(),
+            strip: strip
+                .pixels
+                .iter()
+                .map(|pixel| *pixel == Some(BinaryColor::On))
+                .collect(),
+            width,
+            style,
+            started_at: Instant::now(),
+            offset: 0,
+            finished: false,
+        }
+    }
+
+    /// Goes back to the start and waits before scrolling again
+    pub fn restart(&mut self) {
+        self.started_at = Instant::now();
+        self.offset = 0;
+        self.finished = false;
+    }
+
+    /// Moves the text to where it should be by now, returns `true` if it moved
+    pub fn update(&mut self) -> bool {
+        let (offset, finished) = self.position(self.started_at.elapsed());
+        let moved = offset != self.offset;
+
+        self.offset = offset;
+        self.finished = finished;
+        moved
+    }
+
+    /// Whether the text is back at the start after scrolling, or doesn't need to scroll at all
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// How far the text has to move to show its end
+    fn overflow(&self) -> u32 {
+        self.strip_size.width.saturating_sub(self.width)
+    }
+
+    /// Offset `elapsed` after starting and whether the scroll is over
+    fn position(&self, elapsed: Duration) -> (u32, bool) {
+        let overflow = self.overflow();
+        if overflow == 0 {
+            return (0, true);
+        }
+
+        let pause = self.style.pause.as_secs_f32();
+        let speed = self.style.speed.max(f32::EPSILON);
+        let moving = elapsed.as_secs_f32() - pause;
+        if moving <= 0. {
+            return (0, false);
+        }
+
+        let moved = (moving * speed) as u32;
+        // Time it takes to reach the end and wait there
+        let reached_end = overflow as f32 / speed + pause;
+
+        match self.style.mode {
+            MarqueeMode::Loop if moved >= self.strip_size.width => (0, true),
+            MarqueeMode::Loop => (moved, false),
+            _ if moved < overflow => (moved, false),
+            _ if moving < reached_end => (overflow, false),
+            MarqueeMode::PauseAtEnds => (0, true),
+            MarqueeMode::PingPong => {
+                let back = ((moving - reached_end) * speed) as u32;
+                if back >= overflow {
+                    (0, true)
+                } else {
+                    (overflow - back, false)
+                }
+            }
+        }
+    }
+
+    pub(crate) fn draw<D: DrawTarget<Color = Rgb565>>(
+        &self,
+        target: &mut D,
+        top_left: Point,
+        color: Rgb565,
+    ) where
+        D::Error: Debug,
+    {
+        let strip_size = self.strip_size;
+        let visible = Size::new(self.width.min(strip_size.width), strip_size.height);
+
+        target
+            .draw_iter(
+                Rectangle::new(Point::zero(), visible)
+                    .points()
+                    .filter(|point| {
+                        let mut x = point.x as u32 + self.offset;
+                        if self.style.mode == MarqueeMode::Loop {
+                            x %= strip_size.width;
+                        }
+
+                        let i = (x + point.y as u32 * strip_size.width) as usize;
+                        self.strip.get(i) == Some(&true)
+                    })
+                    .map(|point| Pixel(top_left + point, color)),
+            )
+            .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::TITLE_FONTS;
+
+    const TEXT: &str = "A title far too long to fit";
+    const WIDTH: u32 = 50;
+    /// Pixels per second
+    const SPEED: f32 = 10.;
+    const PAUSE: f32 = 2.;
+
+    fn marquee(text: &str, mode: MarqueeMode) -> Marquee {
+        let style = MarqueeStyle {
+            mode,
+            speed: SPEED,
+            pause: Duration::from_secs_f32(PAUSE),
+        };
+        Marquee::new(text, &TITLE_FONTS, WIDTH, style)
+    }
+
+    /// Half a pixel after moving `pixels` once the pause is over, so rounding can't go either way
+    fn after(pixels: u32) -> Duration {
+        Duration::from_secs_f32(PAUSE + (pixels as f32 + 0.5) / SPEED)
+    }
+
+    #[test]
+    fn text_that_fits_stays_put() {
+        for mode in [
+            MarqueeMode::Loop,
+            MarqueeMode::PingPong,
+            MarqueeMode::PauseAtEnds,
+        ] {
+            let marquee = marquee("Short", mode);
+            assert_eq!(marquee.overflow(), 0);
+
+            for secs in [0, 1, 10, 100] {
+                assert_eq!(
+                    marquee.position(Duration::from_secs(secs)),
+                    (0, true),
+                    "{mode:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn pauses_at_the_start() {
+        for mode in [
+            MarqueeMode::Loop,
+            MarqueeMode::PingPong,
+            MarqueeMode::PauseAtEnds,
+        ] {
+            let marquee = marquee(TEXT, mode);
+
+            assert_eq!(marquee.position(Duration::ZERO), (0, false), "{mode:?}");
+            assert_eq!(
+                marquee.position(Duration::from_secs_f32(PAUSE - 0.1)),
+                (0, false),
+                "{mode:?}"
+            );
+            assert_eq!(marquee.position(after(5)), (5, false), "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn loop_wraps_around_to_the_start() {
+        let marquee = marquee(TEXT, MarqueeMode::Loop);
+        let strip_width = marquee.strip_size.width;
+        // Includes the gap before the start comes around again
+        assert_eq!(strip_width, TITLE_FONTS.width(&format!("{TEXT}{LOOP_GAP}")));
+
+        // Keeps moving past the end without stopping
+        let overflow = marquee.overflow();
+        assert_eq!(marquee.position(after(overflow)), (overflow, false));
+        assert_eq!(
+            marquee.position(after(strip_width - 1)),
+            (strip_width - 1, false)
+        );
+        assert_eq!(marquee.position(after(strip_width)), (0, true));
+    }
+
+    #[test]
+    fn pause_at_ends_waits_at_the_end_then_jumps_back() {
+        let marquee = marquee(TEXT, MarqueeMode::PauseAtEnds);
+        let overflow = marquee.overflow();
+
+        assert_eq!(marquee.position(after(overflow - 1)), (overflow - 1, false));
+        // Pausing at the end
+        assert_eq!(marquee.position(after(overflow)), (overflow, false));
+        assert_eq!(
+            marquee.position(after(overflow) + Duration::from_secs_f32(PAUSE - 0.1)),
+            (overflow, false)
+        );
+        assert_eq!(
+            marquee.position(after(overflow) + Duration::from_secs_f32(PAUSE)),
+            (0, true)
+        );
+    }
+
+    #[test]
+    fn ping_pong_waits_at_the_end_then_scrolls_back() {
+        let marquee = marquee(TEXT, MarqueeMode::PingPong);
+        let overflow = marquee.overflow();
+        let back_at =
+            |pixels: u32| after(overflow) + Duration::from_secs_f32(PAUSE + pixels as f32 / SPEED);
+
+        assert_eq!(
+            marquee.position(after(overflow) + Duration::from_secs_f32(PAUSE - 0.1)),
+            (overflow, false)
+        );
+        assert_eq!(marquee.position(back_at(5)), (overflow - 5, false));
+        assert_eq!(marquee.position(back_at(overflow - 1)), (1, false));
+        assert_eq!(marquee.position(back_at(overflow)), (0, true));
+    }
+}
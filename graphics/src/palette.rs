@@ -0,0 +1,325 @@
+use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+use image::{ImageBuffer, Rgb};
+use serde::{Deserialize, Serialize};
+
+use crate::{rgb888_to_rgb565, theme::hex_color};
+
+/// Number of boxes median cut splits the cover's colors into
+const PALETTE_BOXES: usize = 8;
+/// Only look at every nth pixel, covers are smooth enough that this barely changes the result
+const SAMPLE_STEP: usize = 3;
+/// Backgrounds brighter than this make white text hard to read
+const MAX_BACKGROUND_LUMINANCE: f32 = 0.04;
+/// WCAG contrast needed for the text
+pub(crate) const MIN_TEXT_CONTRAST: f32 = 4.5;
+/// WCAG contrast needed for the progress bar, which is thick enough to get away with less
+const MIN_ACCENT_CONTRAST: f32 = 3.;
+
+/// Colors used to draw the current track
+///
+/// Colors left out when deserializing are taken from [`Theme::dark`](crate::Theme::dark).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Palette {
+    #[serde(with = "hex_color")]
+    pub background: Rgb565,
+    #[serde(with = "hex_color")]
+    pub text: Rgb565,
+    /// Played part of the progress bar
+    #[serde(with = "hex_color")]
+    pub accent: Rgb565,
+    /// Remaining part of the progress bar
+    #[serde(with = "hex_color")]
+    pub remaining: Rgb565,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            background: Rgb565::new(0, 0, 0),
+            text: Rgb565::new(255, 255, 255),
+            accent: rgb888_to_rgb565(0x1d, 0xb9, 0x54),
+            remaining: rgb888_to_rgb565(160, 160, 160),
+        }
+    }
+}
+
+impl Palette {
+    /// Picks a dominant and accent color from the cover with median cut, adjusted to stay readable
+    pub fn from_image(image: &ImageBuffer<Rgb<u8>, Vec<u8>>) -> Self {
+        let pixels = image
+            .pixels()
+            .step_by(SAMPLE_STEP)
+            .map(|pixel| pixel.0)
+            .collect::<Vec<_>>();
+
+        let Some((dominant, accent)) = dominant_and_accent(pixels) else {
+            return Self::default();
+        };
+
+        let background = darken_to(dominant, MAX_BACKGROUND_LUMINANCE);
+        let text = [[255, 255, 255], [0, 0, 0]]
+            .into_iter()
+            .max_by(|a, b| contrast(*a, background).total_cmp(&contrast(*b, background)))
+            .unwrap();
+        let text = mix_until_contrast(dominant, text, background, MIN_TEXT_CONTRAST);
+        let accent = mix_until_contrast(accent, text, background, MIN_ACCENT_CONTRAST);
+        let remaining = mix(background, text, 0.6);
+
+        Self {
+            background: to_rgb565(background),
+            text: to_rgb565(text),
+            accent: to_rgb565(accent),
+            remaining: to_rgb565(remaining),
+        }
+    }
+}
+
+/// Most common color and the most colorful color which is not the dominant one
+fn dominant_and_accent(pixels: Vec<[u8; 3]>) -> Option<([u8; 3], [u8; 3])> {
+    if pixels.is_empty() {
+        return None;
+    }
+
+    let mut boxes = vec![pixels];
+    while boxes.len() < PALETTE_BOXES {
+        // Split the box with the widest range in any channel
+        let Some((index, channel, _)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(i, colors)| {
+                let (channel, range) = widest_channel(colors);
+                (i, channel, range)
+            })
+            .max_by_key(|(_, _, range)| *range)
+        else {
+            break;
+        };
+
+        let colors = &mut boxes[index];
+        colors.sort_unstable_by_key(|color| color[channel]);
+        let upper = colors.split_off(colors.len() / 2);
+        boxes.push(upper);
+    }
+
+    let summaries = boxes
+        .iter()
+        .map(|colors| (average(colors), colors.len()))
+        .collect::<Vec<_>>();
+
+    let (dominant, _) = *summaries.iter().max_by_key(|(_, count)| *count)?;
+    let accent = summaries
+        .iter()
+        .filter(|(color, _)| distance(*color, dominant) > 48 * 48)
+        .max_by(|(a, a_count), (b, b_count)| {
+            let a = saturation(*a) * (*a_count as f32).sqrt();
+            let b = saturation(*b) * (*b_count as f32).sqrt();
+            a.total_cmp(&b)
+        })
+        .map_or(dominant, |(color, _)| *color);
+
+    Some((dominant, accent))
+}
+
+fn widest_channel(colors: &[[u8; 3]]) -> (usize, u8) {
+    (0..3)
+        .map(|c| {
+            let (min, max) = colors.iter().fold((u8::MAX, u8::MIN), |(min, max), color| {
+                (min.min(color[c]), max.max(color[c]))
+            });
+            (c, max - min)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap()
+}
+
+fn average(colors: &[[u8; 3]]) -> [u8; 3] {
+    let sums = colors.iter().fold([0u32; 3], |mut sums, color| {
+        for c in 0..3 {
+            sums[c] += color[c] as u32;
+        }
+        sums
+    });
+
+    sums.map(|sum| (sum / colors.len() as u32) as u8)
+}
+
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    (0..3)
+        .map(|c| (a[c] as i32 - b[c] as i32).pow(2) as u32)
+        .sum()
+}
+
+fn saturation([r, g, b]: [u8; 3]) -> f32 {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+
+    if max == 0 {
+        0.
+    } else {
+        (max - min) as f32 / max as f32
+    }
+}
+
+/// WCAG relative luminance
+fn luminance(color: [u8; 3]) -> f32 {
+    let [r, g, b] = color.map(|c| {
+        let c = c as f32 / 255.;
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    });
+
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+/// WCAG contrast ratio, from 1 to 21
+pub(crate) fn contrast(a: [u8; 3], b: [u8; 3]) -> f32 {
+    let (a, b) = (luminance(a), luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+pub(crate) fn mix(from: [u8; 3], to: [u8; 3], amount: f32) -> [u8; 3] {
+    let mut result = [0; 3];
+    for c in 0..3 {
+        result[c] = (from[c] as f32 + (to[c] as f32 - from[c] as f32) * amount).round() as u8;
+    }
+    result
+}
+
+/// [`mix`] for colors that are already drawn, channel by channel in RGB565
+pub(crate) fn mix_rgb565(from: Rgb565, to: Rgb565, amount: f32) -> Rgb565 {
+    let [r, g, b] = mix(
+        [from.r(), from.g(), from.b()],
+        [to.r(), to.g(), to.b()],
+        amount,
+    );
+    Rgb565::new(r, g, b)
+}
+
+fn darken_to(mut color: [u8; 3], max_luminance: f32) -> [u8; 3] {
+    while luminance(color) > max_luminance {
+        color = color.map(|c| (c as f32 * 0.85) as u8);
+    }
+    color
+}
+
+/// Black behind light `text`, white behind dark `text`, whichever it stands out from more
+pub(crate) fn fade_target(text: [u8; 3]) -> [u8; 3] {
+    let (black, white) = ([0, 0, 0], [255, 255, 255]);
+    if contrast(text, black) >= contrast(text, white) {
+        black
+    } else {
+        white
+    }
+}
+
+/// Moves `color` towards [`fade_target`] until `text` drawn on top of it has at least
+/// `min_contrast`
+pub(crate) fn fade_until_contrast(color: [u8; 3], text: [u8; 3], min_contrast: f32) -> [u8; 3] {
+    mix_until_contrast(color, fade_target(text), text, min_contrast)
+}
+
+/// Moves `color` towards `target` until it has enough contrast against `background`
+fn mix_until_contrast(
+    color: [u8; 3],
+    target: [u8; 3],
+    background: [u8; 3],
+    min_contrast: f32,
+) -> [u8; 3] {
+    (0..=10)
+        .map(|step| mix(color, target, step as f32 / 10.))
+        .find(|color| contrast(*color, background) >= min_contrast)
+        .unwrap_or(target)
+}
+
+fn to_rgb565([r, g, b]: [u8; 3]) -> Rgb565 {
+    rgb888_to_rgb565(r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::pixelcolor::Rgb888;
+
+    use super::*;
+
+    /// Lower than the contrast asked for, RGB565 moves the colors a little
+    const DRAWN_TOLERANCE: f32 = 0.2;
+
+    const NAVY: [u8; 3] = [20, 40, 120];
+    const ORANGE: [u8; 3] = [240, 140, 20];
+
+    /// `first` on the left `share` of the image, `second` on the rest
+    fn two_colors(first: [u8; 3], second: [u8; 3], share: f32) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+        ImageBuffer::from_fn(60, 60, |x, _| {
+            Rgb(if (x as f32) < 60. * share {
+                first
+            } else {
+                second
+            })
+        })
+    }
+
+    fn rgb888(color: Rgb565) -> [u8; 3] {
+        let color = Rgb888::from(color);
+        [color.r(), color.g(), color.b()]
+    }
+
+    #[test]
+    fn two_colors_give_both_colors() {
+        let pixels = two_colors(NAVY, ORANGE, 0.7)
+            .pixels()
+            .map(|p| p.0)
+            .collect();
+        assert_eq!(dominant_and_accent(pixels), Some((NAVY, ORANGE)));
+
+        // Dominant is whichever covers more
+        let pixels = two_colors(NAVY, ORANGE, 0.3)
+            .pixels()
+            .map(|p| p.0)
+            .collect();
+        assert_eq!(dominant_and_accent(pixels), Some((ORANGE, NAVY)));
+    }
+
+    #[test]
+    fn one_color_is_its_own_accent() {
+        let pixels = vec![NAVY; 100];
+        assert_eq!(dominant_and_accent(pixels), Some((NAVY, NAVY)));
+        assert_eq!(dominant_and_accent(Vec::new()), None);
+    }
+
+    #[test]
+    fn text_and_accent_stand_out_from_the_background() {
+        let covers = [
+            two_colors(NAVY, ORANGE, 0.7),
+            two_colors(ORANGE, NAVY, 0.7),
+            two_colors([255, 255, 255], [250, 240, 0], 0.5),
+            two_colors([0, 0, 0], [10, 10, 10], 0.5),
+            two_colors([128, 128, 128], [140, 120, 130], 0.5),
+            two_colors([0, 200, 0], [200, 0, 200], 0.6),
+        ];
+
+        for cover in covers {
+            let palette = Palette::from_image(&cover);
+            let background = rgb888(palette.background);
+            let text = contrast(rgb888(palette.text), background);
+            let accent = contrast(rgb888(palette.accent), background);
+
+            assert!(
+                luminance(background) <= MAX_BACKGROUND_LUMINANCE + 0.01,
+                "{palette:?}"
+            );
+            assert!(
+                text >= MIN_TEXT_CONTRAST - DRAWN_TOLERANCE,
+                "{text} {palette:?}"
+            );
+            assert!(
+                accent >= MIN_ACCENT_CONTRAST - DRAWN_TOLERANCE,
+                "{accent} {palette:?}"
+            );
+        }
+    }
+}
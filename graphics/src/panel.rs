@@ -0,0 +1,167 @@
+use embedded_graphics::{
+    pixelcolor::{raw::RawU16, Rgb565},
+    prelude::*,
+};
+
+/// Order the panel expects the red and blue channels in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorOrder {
+    #[default]
+    Rgb,
+    Bgr,
+}
+
+/// Order the panel expects the two bytes of each pixel in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ByteOrder {
+    #[default]
+    Big,
+    Little,
+}
+
+/// Describes how a panel interprets the pixels it is sent, so colors can be corrected in software
+///
+/// Everything in this crate is drawn with the logical color and converted with [`PanelFormat::to_panel`]
+/// right before it is sent to the display, which assumes the driver sends pixels big endian as-is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PanelFormat {
+    pub color_order: ColorOrder,
+    pub byte_order: ByteOrder,
+    /// Panel shows the inverse of every color
+    ///
+    /// Flipped in software instead of sending INVON, it's one more operation on a pixel that is
+    /// converted for the panel anyway, and the simulator and [`crate::Framebuffer`] see the same
+    /// bytes the panel gets.
+    pub inverted: bool,
+}
+
+impl PanelFormat {
+    pub const fn new(color_order: ColorOrder, byte_order: ByteOrder, inverted: bool) -> Self {
+        Self {
+            color_order,
+            byte_order,
+            inverted,
+        }
+    }
+
+    /// Converts a logical color into the color that has to be sent for the panel to show it
+    pub fn to_panel(&self, color: Rgb565) -> Rgb565 {
+        let color = match self.color_order {
+            ColorOrder::Rgb => color,
+            ColorOrder::Bgr => Rgb565::new(color.b(), color.g(), color.r()),
+        };
+
+        let mut raw = color.into_storage();
+        if self.inverted {
+            raw = !raw;
+        }
+        if self.byte_order == ByteOrder::Little {
+            raw = raw.swap_bytes();
+        }
+
+        Rgb565::from(RawU16::new(raw))
+    }
+
+    /// Inverse of [`PanelFormat::to_panel`]
+    pub fn from_panel(&self, color: Rgb565) -> Rgb565 {
+        let mut raw = color.into_storage();
+        if self.byte_order == ByteOrder::Little {
+            raw = raw.swap_bytes();
+        }
+        if self.inverted {
+            raw = !raw;
+        }
+
+        let color = Rgb565::from(RawU16::new(raw));
+        match self.color_order {
+            ColorOrder::Rgb => color,
+            ColorOrder::Bgr => Rgb565::new(color.b(), color.g(), color.r()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::primitives::Rectangle;
+    use image::{ImageBuffer, Rgb};
+
+    use super::*;
+    use crate::{
+        draw_album_cover, draw_paused_cover, rgb888_to_rgb565, Artwork, Dither, Framebuffer,
+        Layout, Orientation, Palette, Theme,
+    };
+
+    const FORMATS: [PanelFormat; 4] = [
+        PanelFormat::new(ColorOrder::Rgb, ByteOrder::Big, false),
+        PanelFormat::new(ColorOrder::Bgr, ByteOrder::Big, false),
+        PanelFormat::new(ColorOrder::Rgb, ByteOrder::Little, false),
+        PanelFormat::new(ColorOrder::Bgr, ByteOrder::Little, true),
+    ];
+
+    /// Logical color drawn in the middle of the cover
+    fn cover_center(format: PanelFormat, paused: bool) -> Rgb565 {
+        let mut display = Framebuffer::new(Size::new(128, 160), Rgb565::BLACK);
+        let layout = Layout::new(
+            display.bounding_box(),
+            Orientation::Portrait,
+            &Theme::default(),
+        );
+        let side = layout.cover.size.width;
+        let image = ImageBuffer::from_pixel(side, side, Rgb([200, 100, 50]));
+        let artwork = Artwork::new(&image, Dither::None);
+
+        let draw = if paused {
+            draw_paused_cover
+        } else {
+            draw_album_cover
+        };
+        let palette = Palette::default();
+        draw(
+            &mut display,
+            &layout,
+            Some(&artwork.cover),
+            palette,
+            None,
+            format,
+        );
+
+        // Left of the pause sign
+        let point = layout.cover.top_left + Point::new(4, side as i32 / 2);
+        format.from_panel(display.save(&Rectangle::new(point, Size::new(1, 1)))[0])
+    }
+
+    #[test]
+    fn round_trips_every_format() {
+        let color = Rgb565::new(25, 50, 6);
+        for format in FORMATS {
+            assert_eq!(
+                format.from_panel(format.to_panel(color)),
+                color,
+                "{format:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn inverts_every_bit() {
+        let format = PanelFormat::new(ColorOrder::Rgb, ByteOrder::Big, true);
+        assert_eq!(format.to_panel(Rgb565::BLACK), Rgb565::WHITE);
+    }
+
+    #[test]
+    fn covers_are_converted_for_the_panel_once() {
+        let expected = rgb888_to_rgb565(200, 100, 50);
+        for format in FORMATS {
+            assert_eq!(cover_center(format, false), expected, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn paused_covers_are_converted_for_the_panel_once() {
+        let expected = cover_center(PanelFormat::default(), true);
+        assert_ne!(expected, rgb888_to_rgb565(200, 100, 50));
+        for format in FORMATS {
+            assert_eq!(cover_center(format, true), expected, "{format:?}");
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use embedded_canvas::Canvas;
+use embedded_graphics::{
+    mono_font::{ascii::FONT_10X20, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{Ellipse, Line, PrimitiveStyle, Rectangle},
+    text::{Baseline, Text},
+};
+use image::{ImageBuffer, Rgb};
+
+use crate::palette::mix;
+
+/// Glyphs are drawn small and scaled up by this much
+const GLYPH_SCALE: u32 = 3;
+
+/// Deterministic cover for tracks without one, generated from a hash of `album`, or of the track
+/// `name` if it isn't played from an album, so every track of an album gets the same one
+///
+/// Draws a gradient with a pattern and the initials of the name, or a note if the initials can't
+/// be drawn.
+pub fn placeholder_cover(
+    name: &str,
+    album: Option<&str>,
+    size: u32,
+) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    let name = album.unwrap_or(name);
+    let hash = fnv1a(name.as_bytes());
+
+    let hue = (hash % 360) as f32;
+    let start = hsv_to_rgb(hue, 0.6, 0.8);
+    let end = hsv_to_rgb((hue + 40. + ((hash >> 9) % 80) as f32) % 360., 0.7, 0.4);
+    let pattern = (hash >> 17) % 3;
+
+    let mut image = ImageBuffer::from_fn(size, size, |x, y| {
+        let amount = (x + y) as f32 / (2 * size.max(1)) as f32;
+        let mut color = mix(start, end, amount);
+
+        let darken = match pattern {
+            // Diagonal stripes
+            1 => ((x + y) / 12).is_multiple_of(2),
+            // Rings around the top left corner
+            2 => (((x * x + y * y) as f32).sqrt() as u32 / 10).is_multiple_of(2),
+            _ => false,
+        };
+        if darken {
+            color = color.map(|c| (c as f32 * 0.85) as u8);
+        }
+
+        Rgb(color)
+    });
+
+    let mask = glyph_mask(name);
+    let mask_size = mask.bounding_box().size;
+    let offset = (
+        (size as i32 - (mask_size.width * GLYPH_SCALE) as i32) / 2,
+        (size as i32 - (mask_size.height * GLYPH_SCALE) as i32) / 2,
+    );
+
+    // Shadow first so the glyph stays readable on light gradients
+    for (shift, shade) in [(GLYPH_SCALE as i32 / 2, None), (0, Some([255, 255, 255]))] {
+        for (i, pixel) in mask.pixels.iter().enumerate() {
+            if *pixel != Some(BinaryColor::On) {
+                continue;
+            }
+            let point = Point::new(
+                (i as u32 % mask_size.width) as i32,
+                (i as u32 / mask_size.width) as i32,
+            );
+
+            for dy in 0..GLYPH_SCALE as i32 {
+                for dx in 0..GLYPH_SCALE as i32 {
+                    let x = offset.0 + point.x * GLYPH_SCALE as i32 + dx + shift;
+                    let y = offset.1 + point.y * GLYPH_SCALE as i32 + dy + shift;
+                    if x < 0 || y < 0 || x >= size as i32 || y >= size as i32 {
+                        continue;
+                    }
+
+                    let pixel = image.get_pixel_mut(x as u32, y as u32);
+                    pixel.0 = shade.unwrap_or_else(|| pixel.0.map(|c| c / 2));
+                }
+            }
+        }
+    }
+
+    image
+}
+
+/// Initials of the first two words, or a note for titles the font can't draw
+fn glyph_mask(name: &str) -> Canvas<BinaryColor> {
+    let initials = name
+        .split_whitespace()
+        .filter_map(|word| word.chars().next())
+        .filter(|c| c.is_alphanumeric())
+        .take(2)
+        .flat_map(char::to_uppercase)
+        .collect::<String>();
+
+    if !initials.is_empty() && initials.is_ascii() {
+        let style = MonoTextStyle::new(&FONT_10X20, BinaryColor::On);
+        let text = Text::with_baseline(&initials, Point::zero(), style, Baseline::Top);
+
+        let mut canvas = Canvas::new(text.bounding_box().size);
+        text.draw(&mut canvas).unwrap();
+        return canvas;
+    }
+
+    let mut canvas = Canvas::new(Size::new(14, 20));
+    let style = PrimitiveStyle::with_fill(BinaryColor::On);
+    Ellipse::new(Point::new(0, 13), Size::new(8, 7))
+        .into_styled(style)
+        .draw(&mut canvas)
+        .unwrap();
+    Rectangle::new(Point::new(6, 1), Size::new(2, 16))
+        .into_styled(style)
+        .draw(&mut canvas)
+        .unwrap();
+    Line::new(Point::new(7, 1), Point::new(12, 7))
+        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 2))
+        .draw(&mut canvas)
+        .unwrap();
+    canvas
+}
+
+/// FNV-1a, used since it gives the same hash on every platform and build
+fn fnv1a(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c9dc5, |hash, byte| {
+        (hash ^ *byte as u32).wrapping_mul(0x01000193)
+    })
+}
+
+fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> [u8; 3] {
+    let chroma = value * saturation;
+    let x = chroma * (1. - ((hue / 60.) % 2. - 1.).abs());
+    let m = value - chroma;
+
+    let (r, g, b) = match hue as u32 / 60 {
+        0 => (chroma, x, 0.),
+        1 => (x, chroma, 0.),
+        2 => (0., chroma, x),
+        3 => (0., x, chroma),
+        4 => (x, 0., chroma),
+        _ => (chroma, 0., x),
+    };
+
+    [r, g, b].map(|c| ((c + m) * 255.).round() as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: u32 = 64;
+
+    #[test]
+    fn same_track_gets_the_same_cover() {
+        assert_eq!(
+            placeholder_cover("Song", None, SIZE),
+            placeholder_cover("Song", None, SIZE)
+        );
+        assert_eq!(
+            placeholder_cover("Song", Some("Album"), SIZE),
+            placeholder_cover("Song", Some("Album"), SIZE)
+        );
+    }
+
+    #[test]
+    fn other_tracks_get_other_covers() {
+        assert_ne!(
+            placeholder_cover("Song", None, SIZE),
+            placeholder_cover("Another song", None, SIZE)
+        );
+        assert_ne!(
+            placeholder_cover("Song", Some("Album"), SIZE),
+            placeholder_cover("Song", Some("Another album"), SIZE)
+        );
+    }
+
+    #[test]
+    fn tracks_of_an_album_share_its_cover() {
+        assert_eq!(
+            placeholder_cover("First song", Some("Album"), SIZE),
+            placeholder_cover("Second song", Some("Album"), SIZE)
+        );
+        assert_ne!(
+            placeholder_cover("Song", Some("Album"), SIZE),
+            placeholder_cover("Song", None, SIZE)
+        );
+    }
+
+    #[test]
+    fn hash_is_the_same_everywhere() {
+        // Reference values of 32 bit FNV-1a
+        assert_eq!(fnv1a(b""), 0x811c9dc5);
+        assert_eq!(fnv1a(b"a"), 0xe40c292c);
+        assert_eq!(fnv1a(b"foobar"), 0xbf9cf968);
+    }
+}
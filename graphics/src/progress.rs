@@ -0,0 +1,525 @@
+use std::f32::consts::TAU;
+
+use embedded_graphics::{
+    prelude::*,
+    primitives::{Circle, PrimitiveStyle, Rectangle},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{Backdrop, Framebuffer, Layer, Layout, Palette, PanelFormat, Spacing};
+
+/// How the progress of the current track is shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProgressStyle {
+    /// Bar with a knob at the current position
+    #[default]
+    Bar,
+    /// One pixel line without a knob
+    Line,
+    /// Row of blocks which fill up one after another
+    Segments,
+    /// Border around the cover, filling up clockwise from the top
+    Ring,
+    /// Bars of varying height like an audio waveform
+    Waveform,
+}
+
+impl ProgressStyle {
+    pub const ALL: [ProgressStyle; 5] = [
+        ProgressStyle::Bar,
+        ProgressStyle::Line,
+        ProgressStyle::Segments,
+        ProgressStyle::Ring,
+        ProgressStyle::Waveform,
+    ];
+
+    pub fn indicator(self) -> &'static dyn ProgressIndicator {
+        match self {
+            ProgressStyle::Bar => &BarIndicator,
+            ProgressStyle::Line => &LineIndicator,
+            ProgressStyle::Segments => &SegmentsIndicator,
+            ProgressStyle::Ring => &RingIndicator,
+            ProgressStyle::Waveform => &WaveformIndicator,
+        }
+    }
+}
+
+/// Draws the progress of the current track, used by [`crate::draw_current_progress`]
+pub trait ProgressIndicator {
+    /// Part of the screen drawn over on every update
+    fn area(&self, layout: &Layout) -> Rectangle {
+        layout.progress_area()
+    }
+
+    /// Drawn on top of something else, pixels left empty keep whatever is already there
+    /// instead of being filled with the background
+    fn is_overlay(&self) -> bool {
+        false
+    }
+
+    /// Draws `progress` from 0 to 1 onto `layer`, which covers [`ProgressIndicator::area`]
+    fn draw(
+        &self,
+        layer: &mut Layer,
+        layout: &Layout,
+        spacing: &Spacing,
+        progress: f32,
+        palette: Palette,
+    );
+}
+
+struct BarIndicator;
+
+impl ProgressIndicator for BarIndicator {
+    fn draw(
+        &self,
+        layer: &mut Layer,
+        layout: &Layout,
+        spacing: &Spacing,
+        progress: f32,
+        palette: Palette,
+    ) {
+        let track = Track::new(layout, spacing, progress);
+        track.draw_line(layer, spacing.thickness, palette);
+
+        Circle::with_center(
+            Point::new(track.position, track.center_y),
+            spacing.circle_radius * 2 + 1,
+        )
+        .into_styled(PrimitiveStyle::with_fill(palette.accent))
+        .draw(layer)
+        .unwrap();
+    }
+}
+
+struct LineIndicator;
+
+impl ProgressIndicator for LineIndicator {
+    fn draw(
+        &self,
+        layer: &mut Layer,
+        layout: &Layout,
+        spacing: &Spacing,
+        progress: f32,
+        palette: Palette,
+    ) {
+        Track::new(layout, spacing, progress).draw_line(layer, 1, palette);
+    }
+}
+
+struct SegmentsIndicator;
+
+impl ProgressIndicator for SegmentsIndicator {
+    fn draw(
+        &self,
+        layer: &mut Layer,
+        layout: &Layout,
+        spacing: &Spacing,
+        progress: f32,
+        palette: Palette,
+    ) {
+        const GAP: u32 = 2;
+
+        let track = Track::new(layout, spacing, progress);
+        let segment = Size::new(spacing.thickness * 3, spacing.circle_radius * 2 + 1);
+        let count = (track.width + GAP) / (segment.width + GAP);
+        if count == 0 {
+            return;
+        }
+        let filled = (count as f32 * progress).round() as u32;
+        // Leftover space is split on both ends
+        let used = count * (segment.width + GAP) - GAP;
+        let left = track.left + ((track.width - used) / 2) as i32;
+
+        for i in 0..count {
+            let color = if i < filled {
+                palette.accent
+            } else {
+                palette.remaining
+            };
+            Rectangle::new(
+                Point::new(
+                    left + (i * (segment.width + GAP)) as i32,
+                    track.center_y - (segment.height / 2) as i32,
+                ),
+                segment,
+            )
+            .into_styled(PrimitiveStyle::with_fill(color))
+            .draw(layer)
+            .unwrap();
+        }
+    }
+}
+
+struct RingIndicator;
+
+impl ProgressIndicator for RingIndicator {
+    fn area(&self, layout: &Layout) -> Rectangle {
+        layout.ring.unwrap_or(layout.cover)
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+
+    fn draw(
+        &self,
+        layer: &mut Layer,
+        layout: &Layout,
+        spacing: &Spacing,
+        progress: f32,
+        palette: Palette,
+    ) {
+        let cover = self.area(layout);
+        let thickness = spacing.thickness as i32;
+        let (left, top) = (cover.top_left.x, cover.top_left.y);
+        let (right, bottom) = (
+            left + cover.size.width as i32,
+            top + cover.size.height as i32,
+        );
+        let center = cover.center();
+
+        let ring = cover.points().filter_map(|point| {
+            let inside = point.x >= left + thickness
+                && point.x < right - thickness
+                && point.y >= top + thickness
+                && point.y < bottom - thickness;
+            if inside {
+                return None;
+            }
+
+            // Clockwise from straight up, y grows downwards
+            let (dx, dy) = (
+                (point.x - center.x) as f32 + 0.5,
+                (point.y - center.y) as f32 + 0.5,
+            );
+            let angle = dx.atan2(-dy).rem_euclid(TAU);
+            let color = if angle < progress * TAU {
+                palette.accent
+            } else {
+                palette.remaining
+            };
+            Some(Pixel(point, color))
+        });
+        layer.draw_iter(ring).unwrap();
+    }
+}
+
+struct WaveformIndicator;
+
+impl ProgressIndicator for WaveformIndicator {
+    fn draw(
+        &self,
+        layer: &mut Layer,
+        layout: &Layout,
+        spacing: &Spacing,
+        progress: f32,
+        palette: Palette,
+    ) {
+        const BAR_WIDTH: u32 = 2;
+        const GAP: u32 = 1;
+
+        let track = Track::new(layout, spacing, progress);
+        let max_height = (layout.progress_bar.size.height * 3 / 4).max(1);
+
+        for i in 0..(track.width + GAP) / (BAR_WIDTH + GAP) {
+            let x = track.left + (i * (BAR_WIDTH + GAP)) as i32;
+            // Same made up shape for every track, it only has to look like audio
+            let level = ((i as f32 * 0.9).sin() * (i as f32 * 0.37).cos()).abs();
+            let height = ((max_height as f32 * (0.25 + 0.75 * level)).round() as u32).max(1);
+            let color = if x < track.position {
+                palette.accent
+            } else {
+                palette.remaining
+            };
+
+            Rectangle::new(
+                Point::new(x, track.center_y - (height / 2) as i32),
+                Size::new(BAR_WIDTH, height),
+            )
+            .into_styled(PrimitiveStyle::with_fill(color))
+            .draw(layer)
+            .unwrap();
+        }
+    }
+}
+
+/// Horizontal span the bar styles are drawn along, [`Layout::progress_bar`] without the padding
+struct Track {
+    left: i32,
+    width: u32,
+    center_y: i32,
+    /// Column reached at the current progress
+    position: i32,
+}
+
+impl Track {
+    fn new(layout: &Layout, spacing: &Spacing, progress: f32) -> Self {
+        let bar = layout.progress_bar;
+        let width = bar.size.width.saturating_sub(spacing.padding * 2);
+        let left = bar.top_left.x + spacing.padding as i32;
+
+        Self {
+            left,
+            width,
+            center_y: bar.top_left.y + (bar.size.height / 2) as i32,
+            position: left + ((width as f32 * progress).round() as u32).min(width) as i32,
+        }
+    }
+
+    /// Played part in the accent color followed by the rest
+    fn draw_line(&self, layer: &mut Layer, thickness: u32, palette: Palette) {
+        let top = self.center_y - (thickness / 2) as i32;
+        let played = (self.position - self.left) as u32;
+
+        Rectangle::new(Point::new(self.left, top), Size::new(played, thickness))
+            .into_styled(PrimitiveStyle::with_fill(palette.accent))
+            .draw(layer)
+            .unwrap();
+        Rectangle::new(
+            Point::new(self.position, top),
+            Size::new(self.width - played, thickness),
+        )
+        .into_styled(PrimitiveStyle::with_fill(palette.remaining))
+        .draw(layer)
+        .unwrap();
+    }
+}
+
+/// Clears the ring around the cover while nothing is playing, does nothing for the other styles
+pub(crate) fn clear_ring(
+    display: &mut Framebuffer,
+    layout: &Layout,
+    palette: Palette,
+    backdrop: Option<&Backdrop>,
+    format: PanelFormat,
+) {
+    let Some(ring) = layout.ring else {
+        return;
+    };
+
+    display
+        .layer(ring, palette.background, backdrop, format)
+        .finish_parts(&layout.ring_parts());
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::pixelcolor::Rgb565;
+
+    use super::*;
+    use crate::{draw_album_cover, draw_current_progress, Framebuffer, Orientation, Theme};
+
+    const DURATION: u32 = 215;
+    const BAR_STYLES: [ProgressStyle; 4] = [
+        ProgressStyle::Bar,
+        ProgressStyle::Line,
+        ProgressStyle::Segments,
+        ProgressStyle::Waveform,
+    ];
+    /// The knob and whole segments move the played share a little
+    const SHARE_TOLERANCE: f32 = 0.1;
+
+    fn ring_theme() -> Theme {
+        Theme {
+            progress_style: ProgressStyle::Ring,
+            ..Theme::default()
+        }
+    }
+
+    /// Black screen with the progress drawn at `progress_secs` of `duration`
+    fn draw_progress(theme: &Theme, progress_secs: u32, duration: u32) -> (Layout, Framebuffer) {
+        let mut display = Framebuffer::new(Size::new(128, 160), Rgb565::BLACK);
+        let layout = Layout::new(display.bounding_box(), Orientation::Portrait, theme);
+        draw_current_progress(
+            &mut display,
+            &layout,
+            theme,
+            progress_secs,
+            duration,
+            theme.palette,
+            None,
+            PanelFormat::default(),
+        );
+
+        (layout, display)
+    }
+
+    /// Every pixel of the ring with where it is relative to the center of the cover
+    fn ring_pixels(layout: &Layout, display: &Framebuffer) -> Vec<(Point, Rgb565)> {
+        let ring = layout.ring.unwrap();
+        let center = ring.center();
+
+        ring.points()
+            .zip(display.save(&ring))
+            .filter(|(point, _)| !layout.cover.contains(*point))
+            .map(|(point, color)| (point - center, color))
+            .collect()
+    }
+
+    /// Share of the bar in the accent color, along the row through its middle
+    fn played_share(style: ProgressStyle, progress_secs: u32, duration: u32) -> f32 {
+        let theme = Theme {
+            progress_style: style,
+            ..Theme::default()
+        };
+        let (layout, display) = draw_progress(&theme, progress_secs, duration);
+        let bar = layout.progress_bar;
+        let row = Rectangle::new(
+            Point::new(
+                bar.top_left.x,
+                bar.top_left.y + (bar.size.height / 2) as i32,
+            ),
+            Size::new(bar.size.width, 1),
+        );
+
+        let pixels = display.save(&row);
+        let count = |color| pixels.iter().filter(|pixel| **pixel == color).count();
+        let (played, remaining) = (count(theme.palette.accent), count(theme.palette.remaining));
+        assert!(played + remaining > 0, "{style:?} drew nothing");
+        played as f32 / (played + remaining) as f32
+    }
+
+    #[test]
+    fn bar_styles_at_start() {
+        for style in BAR_STYLES {
+            let share = played_share(style, 0, DURATION);
+            assert!(share <= SHARE_TOLERANCE, "{style:?}: {share}");
+        }
+    }
+
+    #[test]
+    fn bar_styles_halfway() {
+        for style in BAR_STYLES {
+            let share = played_share(style, DURATION / 2, DURATION);
+            assert!((share - 0.5).abs() <= SHARE_TOLERANCE, "{style:?}: {share}");
+        }
+    }
+
+    #[test]
+    fn bar_styles_at_end() {
+        for style in BAR_STYLES {
+            assert_eq!(played_share(style, DURATION, DURATION), 1., "{style:?}");
+        }
+    }
+
+    #[test]
+    fn bar_styles_without_duration() {
+        for style in BAR_STYLES {
+            let share = played_share(style, 0, 0);
+            assert!(share <= SHARE_TOLERANCE, "{style:?}: {share}");
+        }
+    }
+
+    #[test]
+    fn ring_is_around_the_cover() {
+        let theme = ring_theme();
+        let (layout, _) = draw_progress(&theme, 0, DURATION);
+        let ring = layout.ring.unwrap();
+        let thickness = theme.spacing.thickness as i32;
+
+        assert_eq!(
+            layout.cover.top_left,
+            ring.top_left + Point::new(thickness, thickness)
+        );
+        assert_eq!(
+            layout.cover.bottom_right().unwrap(),
+            ring.bottom_right().unwrap() - Point::new(thickness, thickness)
+        );
+        assert!(layout.cover_area().contains(ring.top_left));
+        assert!(layout.cover_area().contains(ring.bottom_right().unwrap()));
+    }
+
+    #[test]
+    fn ring_at_start() {
+        let theme = ring_theme();
+        let (layout, display) = draw_progress(&theme, 0, DURATION);
+
+        assert!(ring_pixels(&layout, &display)
+            .iter()
+            .all(|(_, color)| *color == theme.palette.remaining));
+    }
+
+    #[test]
+    fn ring_after_a_second() {
+        let theme = ring_theme();
+        let (layout, display) = draw_progress(&theme, 1, DURATION);
+        let pixels = ring_pixels(&layout, &display);
+
+        // Only the first second is played, just right of straight up
+        let played = pixels
+            .iter()
+            .filter(|(_, color)| *color == theme.palette.accent)
+            .collect::<Vec<_>>();
+        assert!(!played.is_empty());
+        assert!(played.len() < pixels.len() / 100);
+        assert!(played
+            .iter()
+            .all(|(offset, _)| offset.x >= 0 && offset.y < 0));
+    }
+
+    #[test]
+    fn ring_halfway() {
+        let theme = ring_theme();
+        let (layout, display) = draw_progress(&theme, DURATION / 2, DURATION);
+
+        // Column straight up and down is where played and remaining meet
+        for (offset, color) in ring_pixels(&layout, &display) {
+            if offset.x > 0 {
+                assert_eq!(color, theme.palette.accent, "{offset:?}");
+            } else if offset.x < 0 && offset.y < 0 {
+                assert_eq!(color, theme.palette.remaining, "{offset:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn ring_at_end() {
+        let theme = ring_theme();
+        let (layout, display) = draw_progress(&theme, DURATION, DURATION);
+
+        assert!(ring_pixels(&layout, &display)
+            .iter()
+            .all(|(_, color)| *color == theme.palette.accent));
+    }
+
+    #[test]
+    fn ring_without_duration() {
+        let theme = ring_theme();
+        let (layout, display) = draw_progress(&theme, 0, 0);
+
+        assert!(ring_pixels(&layout, &display)
+            .iter()
+            .all(|(_, color)| *color == theme.palette.remaining));
+    }
+
+    #[test]
+    fn ring_leaves_cover_alone() {
+        let theme = ring_theme();
+        let (layout, display) = draw_progress(&theme, DURATION / 2, DURATION);
+
+        assert!(display
+            .save(&layout.cover)
+            .iter()
+            .all(|color| *color == Rgb565::BLACK));
+    }
+
+    #[test]
+    fn cover_leaves_ring_alone() {
+        let theme = ring_theme();
+        let (layout, mut display) = draw_progress(&theme, DURATION, DURATION);
+        draw_album_cover(
+            &mut display,
+            &layout,
+            None,
+            theme.palette,
+            None,
+            PanelFormat::default(),
+        );
+
+        assert!(ring_pixels(&layout, &display)
+            .iter()
+            .all(|(_, color)| *color == theme.palette.accent));
+    }
+}
@@ -0,0 +1,94 @@
+use common::{Playing, RepeatState};
+use embedded_graphics::{
+    mono_font::MonoTextStyle,
+    prelude::*,
+    text::{Alignment, Baseline, Text, TextStyleBuilder},
+};
+
+use crate::{
+    icon::{device_icon, Icon, REPEAT, REPEAT_ONE, SHUFFLE},
+    Backdrop, Framebuffer, Layout, Palette, PanelFormat, Theme,
+};
+
+/// Space between icons
+const ICON_GAP: u32 = 4;
+
+/// Shuffle and repeat on the left and the playback device on the right of [`Layout::status`]
+///
+/// Icons of settings which are off are drawn in the remaining color of the progress bar. The name of
+/// the device is only drawn left of its icon if `show_device_name` is set, it's usually redundant.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_status(
+    display: &mut Framebuffer,
+    layout: &Layout,
+    theme: &Theme,
+    playing: &Playing,
+    show_device_name: bool,
+    palette: Palette,
+    backdrop: Option<&Backdrop>,
+    format: PanelFormat,
+) {
+    let area = layout.status_area();
+    let status = layout.status;
+    let top = status.top_left.y + (status.size.height.saturating_sub(Icon::SIZE) / 2) as i32;
+    let left = status.top_left.x;
+    let right = left + status.size.width as i32;
+
+    let mut layer = display.layer(area, palette.background, backdrop, format);
+
+    let shuffle_color = if playing.shuffled {
+        palette.accent
+    } else {
+        palette.remaining
+    };
+    let (repeat, repeat_color) = match playing.repeat {
+        RepeatState::Off => (&REPEAT, palette.remaining),
+        RepeatState::Context => (&REPEAT, palette.accent),
+        RepeatState::Track => (&REPEAT_ONE, palette.accent),
+    };
+    SHUFFLE.draw(&mut layer, Point::new(left, top), shuffle_color);
+    repeat.draw(
+        &mut layer,
+        Point::new(left + (Icon::SIZE + ICON_GAP) as i32, top),
+        repeat_color,
+    );
+
+    let device_left = right - Icon::SIZE as i32;
+    device_icon(&playing.device._type).draw(&mut layer, Point::new(device_left, top), palette.text);
+
+    if show_device_name {
+        let font = theme.text_size.time_font();
+        // Whatever doesn't fit right of the other icons is cut off
+        let name_left = left + ((Icon::SIZE + ICON_GAP) * 2) as i32;
+        let name_right = device_left - ICON_GAP as i32;
+        let fits = (name_right - name_left).max(0) as u32 / font.character_size.width;
+        let name = playing
+            .device
+            .name
+            .chars()
+            .take(fits as usize)
+            .collect::<String>();
+
+        Text::with_text_style(
+            &name,
+            Point::new(
+                name_right,
+                status.top_left.y
+                    + (status
+                        .size
+                        .height
+                        .saturating_sub(font.character_size.height)
+                        / 2) as i32,
+            ),
+            MonoTextStyle::new(font, palette.text),
+            TextStyleBuilder::new()
+                .alignment(Alignment::Right)
+                .baseline(Baseline::Top)
+                .build(),
+        )
+        .draw(&mut layer)
+        .unwrap();
+    }
+
+    layer.finish();
+}
@@ -0,0 +1,262 @@
+use embedded_graphics::mono_font::{ascii, MonoFont};
+use embedded_graphics::pixelcolor::{Rgb565, Rgb888};
+use embedded_graphics::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    rgb888_to_rgb565, FontSet, Palette, ProgressStyle, ARTIST_FONTS, LARGE_ARTIST_FONTS,
+    LARGE_TITLE_FONTS, TITLE_FONTS,
+};
+
+/// Fonts used for the title and artists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextSize {
+    /// 14 pixel titles, 13 pixel artists and 10 pixel times
+    #[default]
+    Regular,
+    /// 20 pixel titles, 18 pixel artists and 13 pixel times, for larger panels
+    Large,
+}
+
+impl TextSize {
+    pub fn title_fonts(self) -> &'static FontSet {
+        match self {
+            TextSize::Regular => &TITLE_FONTS,
+            TextSize::Large => &LARGE_TITLE_FONTS,
+        }
+    }
+
+    pub fn artist_fonts(self) -> &'static FontSet {
+        match self {
+            TextSize::Regular => &ARTIST_FONTS,
+            TextSize::Large => &LARGE_ARTIST_FONTS,
+        }
+    }
+
+    /// Only has to cover digits, colons and minus signs
+    pub fn time_font(self) -> &'static MonoFont<'static> {
+        match self {
+            TextSize::Regular => &ascii::FONT_6X10,
+            TextSize::Large => &ascii::FONT_8X13,
+        }
+    }
+
+    /// Messages like "Not Playing" drawn in the middle of the screen, only ASCII
+    pub fn message_font(self) -> &'static MonoFont<'static> {
+        match self {
+            TextSize::Regular => &ascii::FONT_7X14,
+            TextSize::Large => &ascii::FONT_10X20,
+        }
+    }
+
+    /// Rank of a track in the top tracks, only digits and `#`
+    pub fn rank_font(self) -> &'static MonoFont<'static> {
+        match self {
+            TextSize::Regular => &ascii::FONT_9X15_BOLD,
+            TextSize::Large => &ascii::FONT_10X20,
+        }
+    }
+}
+
+/// Sizes in pixels used by the layout and the progress bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Spacing {
+    /// Space on both ends of the progress bar
+    pub padding: u32,
+    /// Radius of the knob on the progress bar
+    pub circle_radius: u32,
+    /// Thickness of the progress bar and the ring around the cover
+    pub thickness: u32,
+    /// Space kept free on both sides of the cover
+    pub cover_margin: u32,
+    /// Text is inset by this much on both sides
+    pub text_inset: u32,
+    /// Height of the row the progress bar is centered in
+    pub progress_height: u32,
+}
+
+impl Default for Spacing {
+    fn default() -> Self {
+        Self {
+            padding: 10,
+            circle_radius: 4,
+            thickness: 3,
+            cover_margin: 7,
+            text_inset: 2,
+            progress_height: 19,
+        }
+    }
+}
+
+/// Colors, fonts and spacing used to draw everything
+///
+/// Deserializes from JSON like `{"palette": {"background": "#000000", ...}, "text_size": "large"}`,
+/// anything left out is taken from [`Theme::dark`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    /// Used while nothing is playing, and for every track unless `cover_colors` is set
+    pub palette: Palette,
+    /// Pick the colors from each cover instead
+    pub cover_colors: bool,
+    pub text_size: TextSize,
+    pub progress_style: ProgressStyle,
+    pub spacing: Spacing,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    /// Colors follow the cover, white text on black otherwise
+    pub fn dark() -> Self {
+        Self {
+            palette: Palette::default(),
+            cover_colors: true,
+            text_size: TextSize::Regular,
+            progress_style: ProgressStyle::Bar,
+            spacing: Spacing::default(),
+        }
+    }
+
+    /// Black text on white, covers don't change the colors since their palettes are always dark
+    pub fn light() -> Self {
+        Self {
+            palette: Palette {
+                background: Rgb565::WHITE,
+                text: Rgb565::BLACK,
+                accent: rgb888_to_rgb565(0x10, 0x7a, 0x36),
+                remaining: rgb888_to_rgb565(96, 96, 96),
+            },
+            cover_colors: false,
+            ..Self::dark()
+        }
+    }
+
+    /// Pure colors, larger text and a thicker progress bar
+    pub fn high_contrast() -> Self {
+        Self {
+            palette: Palette {
+                background: Rgb565::BLACK,
+                text: Rgb565::WHITE,
+                accent: Rgb565::YELLOW,
+                remaining: rgb888_to_rgb565(128, 128, 128),
+            },
+            cover_colors: false,
+            text_size: TextSize::Large,
+            progress_style: ProgressStyle::Bar,
+            spacing: Spacing {
+                circle_radius: 5,
+                thickness: 5,
+                progress_height: 23,
+                ..Spacing::default()
+            },
+        }
+    }
+
+    /// One of the built in themes, `dark`, `light` or `high-contrast`
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "high-contrast" => Some(Self::high_contrast()),
+            _ => None,
+        }
+    }
+
+    /// Palette to draw a track with, `cover` is the one picked from its cover if it's ready
+    pub fn track_palette(&self, cover: Option<Palette>) -> Palette {
+        match cover {
+            Some(cover) if self.cover_colors => cover,
+            _ => self.palette,
+        }
+    }
+}
+
+/// Colors as `#rrggbb` strings, for `#[serde(with = "...")]`
+pub(crate) mod hex_color {
+    use super::*;
+
+    pub fn serialize<S: serde::Serializer>(
+        color: &Rgb565,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let color = Rgb888::from(*color);
+        serializer.serialize_str(&format!(
+            "#{:02x}{:02x}{:02x}",
+            color.r(),
+            color.g(),
+            color.b()
+        ))
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Rgb565, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        let invalid =
+            || serde::de::Error::custom(format!("expected a color like #1db954, got {text}"));
+
+        let hex = text
+            .strip_prefix('#')
+            .filter(|hex| hex.len() == 6)
+            .ok_or_else(invalid)?;
+        let value = u32::from_str_radix(hex, 16).map_err(|_| invalid())?;
+        let [_, r, g, b] = value.to_be_bytes();
+        Ok(rgb888_to_rgb565(r, g, b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_partial_palette() {
+        let theme: Theme = serde_json::from_str(r##"{"palette": {"accent": "#ff0000"}}"##).unwrap();
+
+        assert_eq!(theme.palette.accent, rgb888_to_rgb565(0xff, 0, 0));
+        assert_eq!(
+            theme.palette,
+            Palette {
+                accent: theme.palette.accent,
+                ..Theme::dark().palette
+            }
+        );
+    }
+
+    #[test]
+    fn parses_partial_theme() {
+        let theme: Theme =
+            serde_json::from_str(r#"{"text_size": "large", "spacing": {"thickness": 5}}"#).unwrap();
+
+        assert_eq!(theme.text_size, TextSize::Large);
+        assert_eq!(theme.spacing.thickness, 5);
+        assert_eq!(theme.palette, Theme::dark().palette);
+        assert_eq!(theme.progress_style, Theme::dark().progress_style);
+    }
+
+    #[test]
+    fn round_trips_built_in_themes() {
+        for name in ["dark", "light", "high-contrast"] {
+            let theme = Theme::by_name(name).unwrap();
+            let json = serde_json::to_string(&theme).unwrap();
+            assert_eq!(
+                serde_json::from_str::<Theme>(&json).unwrap(),
+                theme,
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_colors() {
+        let result = serde_json::from_str::<Theme>(r#"{"palette": {"text": "white"}}"#);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use embedded_graphics::{
+    mono_font::{MonoFont, MonoTextStyle},
+    prelude::*,
+    primitives::Rectangle,
+    text::{Baseline, Text, TextStyleBuilder},
+};
+
+use crate::{Backdrop, Framebuffer, Layout, Palette, PanelFormat, Theme};
+
+/// Elapsed and remaining time under the progress bar, call [`TimeLabels::draw`] every second
+///
+/// Remembers what was drawn last, so only the digits which changed are drawn again.
+#[derive(Debug, Clone, Default)]
+pub struct TimeLabels {
+    drawn: Option<Drawn>,
+}
+
+#[derive(Debug, Clone)]
+struct Drawn {
+    elapsed: String,
+    remaining: String,
+    palette: Palette,
+}
+
+impl TimeLabels {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Draw everything again next time, needed once whatever is behind the labels changed
+    pub fn clear(&mut self) {
+        self.drawn = None;
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &mut self,
+        display: &mut Framebuffer,
+        layout: &Layout,
+        theme: &Theme,
+        progress_secs: u32,
+        duration: u32,
+        palette: Palette,
+        backdrop: Option<&Backdrop>,
+        format: PanelFormat,
+    ) {
+        let font = theme.text_size.time_font();
+        let hours = duration >= 60 * 60;
+        let elapsed = format_time(progress_secs.min(duration), hours);
+        let remaining = format!(
+            "-{}",
+            format_time(duration.saturating_sub(progress_secs), hours)
+        );
+
+        let area = layout.time;
+        let left = Rectangle::new(
+            area.top_left,
+            Size::new(area.size.width / 2, area.size.height),
+        );
+        let right = Rectangle::new(
+            area.top_left + Point::new(left.size.width as i32, 0),
+            Size::new(area.size.width - left.size.width, area.size.height),
+        );
+
+        let remaining_width = remaining.len() as u32 * font.character_size.width;
+        let elapsed_label = Label {
+            text: &elapsed,
+            half: left,
+            start: left.top_left.x,
+            font,
+        };
+        // Right aligned with the end of the bar
+        let remaining_label = Label {
+            text: &remaining,
+            half: right,
+            start: right.top_left.x + right.size.width.saturating_sub(remaining_width) as i32,
+            font,
+        };
+
+        match self.drawn.as_ref().filter(|drawn| drawn.palette == palette) {
+            Some(drawn) => {
+                elapsed_label.update(&drawn.elapsed, display, palette, backdrop, format);
+                remaining_label.update(&drawn.remaining, display, palette, backdrop, format);
+            }
+            // Including the rows below the labels, which nothing else draws over
+            None => draw_labels(
+                layout.time_area(),
+                &[&elapsed_label, &remaining_label],
+                display,
+                palette,
+                backdrop,
+                format,
+            ),
+        }
+
+        self.drawn = Some(Drawn {
+            elapsed,
+            remaining,
+            palette,
+        });
+    }
+}
+
+/// One of the labels, drawn into its half of [`Layout::time`]
+struct Label<'a> {
+    text: &'a str,
+    half: Rectangle,
+    /// First column of the text
+    start: i32,
+    font: &'static MonoFont<'static>,
+}
+
+impl Label<'_> {
+    /// Draws over whatever changed since `previous` was drawn
+    fn update(
+        &self,
+        previous: &str,
+        display: &mut Framebuffer,
+        palette: Palette,
+        backdrop: Option<&Backdrop>,
+        format: PanelFormat,
+    ) {
+        let char_width = self.font.character_size.width;
+
+        // Width changed, e.g. 10:00 to 9:59, clear the whole half
+        if previous.len() != self.text.len() {
+            draw_labels(self.half, &[self], display, palette, backdrop, format);
+            return;
+        }
+
+        // Same width, every digit stays in place
+        for (i, (old, new)) in previous.bytes().zip(self.text.bytes()).enumerate() {
+            if old == new {
+                continue;
+            }
+
+            let cell = Rectangle::new(
+                Point::new(
+                    self.start + (i as u32 * char_width) as i32,
+                    self.half.top_left.y,
+                ),
+                Size::new(char_width, self.half.size.height),
+            );
+            draw_labels(cell, &[self], display, palette, backdrop, format);
+        }
+    }
+}
+
+/// Draws the parts of `labels` inside `area`, filling the rest with the background
+fn draw_labels(
+    area: Rectangle,
+    labels: &[&Label],
+    display: &mut Framebuffer,
+    palette: Palette,
+    backdrop: Option<&Backdrop>,
+    format: PanelFormat,
+) {
+    let mut layer = display.layer(area, palette.background, backdrop, format);
+    for label in labels {
+        Text::with_text_style(
+            label.text,
+            Point::new(label.start, label.half.top_left.y),
+            MonoTextStyle::new(label.font, palette.text),
+            TextStyleBuilder::new().baseline(Baseline::Top).build(),
+        )
+        .draw(&mut layer)
+        .unwrap();
+    }
+    layer.finish();
+}
+
+/// `m:ss`, or `h:mm:ss` if `hours` is set
+fn format_time(secs: u32, hours: bool) -> String {
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+
+    if hours {
+        format!("{h}:{m:02}:{s:02}")
+    } else {
+        format!("{}:{s:02}", secs / 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_minutes_and_seconds() {
+        assert_eq!(format_time(0, false), "0:00");
+        assert_eq!(format_time(59, false), "0:59");
+        assert_eq!(format_time(60, false), "1:00");
+        assert_eq!(format_time(3599, false), "59:59");
+    }
+
+    #[test]
+    fn formats_hours() {
+        assert_eq!(format_time(0, true), "0:00:00");
+        assert_eq!(format_time(3599, true), "0:59:59");
+        assert_eq!(format_time(3600, true), "1:00:00");
+        assert_eq!(format_time(3600 * 10 + 61, true), "10:01:01");
+    }
+
+    #[test]
+    fn keeps_counting_minutes_without_hours() {
+        assert_eq!(format_time(3600, false), "60:00");
+        assert_eq!(format_time(3661, false), "61:01");
+    }
+}
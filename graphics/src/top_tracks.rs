@@ -0,0 +1,343 @@
+use std::time::{Duration, Instant};
+
+use common::{Data, SimpleTrack};
+use embedded_graphics::{
+    mono_font::MonoTextStyle,
+    prelude::*,
+    primitives::{Circle, PrimitiveStyle, Rectangle},
+    text::{Baseline, Text},
+};
+
+use crate::{progress::clear_ring, Backdrop, Framebuffer, Layout, Palette, PanelFormat, Theme};
+
+/// Most tracks shown of each time range before moving on to the next one
+const SHOWN_PER_RANGE: usize = 10;
+/// How long each track is shown by [`TopTracks::update`], every one downloads its cover
+const SHOWN_FOR: Duration = Duration::from_secs(30);
+/// Space around the rank inside its badge
+const BADGE_INSET: u32 = 2;
+/// Space between the dots under the track text
+const DOT_GAP: u32 = 3;
+
+/// Time range the top tracks are counted over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeRange {
+    /// About the last 4 weeks
+    Short,
+    /// About the last 6 months
+    Mid,
+    /// Several years
+    Long,
+}
+
+impl TimeRange {
+    pub const ALL: [TimeRange; 3] = [TimeRange::Short, TimeRange::Mid, TimeRange::Long];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TimeRange::Short => "Top, 4 weeks",
+            TimeRange::Mid => "Top, 6 months",
+            TimeRange::Long => "Top, all time",
+        }
+    }
+
+    pub fn tracks(self, data: &Data) -> &[SimpleTrack] {
+        match self {
+            TimeRange::Short => &data.short_term_top,
+            TimeRange::Mid => &data.mid_term_top,
+            TimeRange::Long => &data.long_term_top,
+        }
+    }
+}
+
+/// Goes through the first few top tracks of every [`TimeRange`], shortest range first
+#[derive(Debug, Clone)]
+pub struct TopTracks {
+    data: Data,
+    /// Index into [`TimeRange::ALL`] and into its tracks of the track shown
+    current: Option<(usize, usize)>,
+    /// When [`TopTracks::update`] last moved on
+    shown_at: Option<Instant>,
+}
+
+impl TopTracks {
+    pub fn new(data: Data) -> Self {
+        Self {
+            data,
+            current: None,
+            shown_at: None,
+        }
+    }
+
+    /// Moves on to the next track once the current one was shown for 30 seconds, or right away
+    /// while none is shown, returns `true` if it did
+    pub fn update(&mut self) -> bool {
+        self.update_at(Instant::now())
+    }
+
+    fn update_at(&mut self, now: Instant) -> bool {
+        let due = match (self.current, self.shown_at) {
+            (Some(_), Some(shown_at)) => now.saturating_duration_since(shown_at) >= SHOWN_FOR,
+            _ => true,
+        };
+
+        if due {
+            self.shown_at = Some(now);
+            self.advance();
+        }
+        due
+    }
+
+    /// Track shown with its range and rank starting at 1
+    pub fn current(&self) -> Option<(TimeRange, usize, &SimpleTrack)> {
+        let (range, index) = self.current?;
+        let range = TimeRange::ALL[range];
+        Some((range, index + 1, &range.tracks(&self.data)[index]))
+    }
+
+    /// Moves on to the next track, ranges without any tracks are skipped
+    pub fn advance(&mut self) -> Option<(TimeRange, usize, &SimpleTrack)> {
+        let (mut range, mut index) = match self.current {
+            Some((range, index)) => (range, index + 1),
+            None => (0, 0),
+        };
+
+        // Back where it started after one more than all ranges
+        for _ in 0..=TimeRange::ALL.len() {
+            if index < shown(TimeRange::ALL[range], &self.data) {
+                self.current = Some((range, index));
+                return self.current();
+            }
+            range = (range + 1) % TimeRange::ALL.len();
+            index = 0;
+        }
+
+        self.current = None;
+        None
+    }
+}
+
+/// Tracks of `range` which are shown
+fn shown(range: TimeRange, data: &Data) -> usize {
+    range.tracks(data).len().min(SHOWN_PER_RANGE)
+}
+
+/// Time range in [`Layout::status`], the rank on the cover and a dot for each track of the range
+/// where the progress bar would be
+///
+/// The cover, title and artists of [`TopTracks::current`] go where they would for a playing track,
+/// drawn with [`crate::draw_album_cover`] and [`crate::draw_current_name_and_artist`]. The rank is
+/// drawn over the cover, so this has to be drawn again after it.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_top_track(
+    display: &mut Framebuffer,
+    layout: &Layout,
+    theme: &Theme,
+    top_tracks: &TopTracks,
+    palette: Palette,
+    backdrop: Option<&Backdrop>,
+    format: PanelFormat,
+) {
+    let Some((range, rank, _)) = top_tracks.current() else {
+        return;
+    };
+
+    let font = theme.text_size.time_font();
+    let area = layout.status_area();
+    let status = layout.status;
+    // Whatever doesn't fit is cut off
+    let fits = status.size.width / font.character_size.width;
+    let label = range
+        .label()
+        .chars()
+        .take(fits as usize)
+        .collect::<String>();
+    let mut layer = display.layer(area, palette.background, backdrop, format);
+    Text::with_baseline(
+        &label,
+        Point::new(
+            status.top_left.x,
+            status.top_left.y
+                + (status
+                    .size
+                    .height
+                    .saturating_sub(font.character_size.height)
+                    / 2) as i32,
+        ),
+        MonoTextStyle::new(font, palette.text),
+        Baseline::Top,
+    )
+    .draw(&mut layer)
+    .unwrap();
+    layer.finish();
+
+    // Badge in the top left corner of the cover
+    let rank_font = theme.text_size.rank_font();
+    let rank = format!("#{rank}");
+    let badge = Size::new(
+        rank.len() as u32 * rank_font.character_size.width + BADGE_INSET * 2,
+        rank_font.character_size.height + BADGE_INSET * 2,
+    );
+    let mut layer = display.layer(
+        Rectangle::new(layout.cover.top_left, badge),
+        palette.background,
+        None,
+        format,
+    );
+    Text::with_baseline(
+        &rank,
+        layout.cover.top_left + Point::new(BADGE_INSET as i32, BADGE_INSET as i32),
+        MonoTextStyle::new(rank_font, palette.accent),
+        Baseline::Top,
+    )
+    .draw(&mut layer)
+    .unwrap();
+    layer.finish();
+
+    // Dots centered where the progress bar would be, the rows under them stay empty
+    let progress = layout.progress_area();
+    let area = Rectangle::with_corners(
+        progress.top_left,
+        layout
+            .time_area()
+            .bottom_right()
+            .unwrap_or(progress.top_left),
+    );
+    let (range_index, index) = top_tracks.current.unwrap_or_default();
+    let count = shown(TimeRange::ALL[range_index], &top_tracks.data) as u32;
+    let bar = layout.progress_bar;
+    // As large as the knob of the progress bar, smaller if they don't fit next to each other
+    let diameter = (theme.spacing.circle_radius * 2 + 1)
+        .min(((bar.size.width + DOT_GAP) / count.max(1)).saturating_sub(DOT_GAP))
+        .max(1);
+    let dots_width = (count * (diameter + DOT_GAP)).saturating_sub(DOT_GAP);
+    let left = bar.center().x - (dots_width / 2) as i32;
+    let top = bar.center().y - (diameter / 2) as i32;
+
+    let mut layer = display.layer(area, palette.background, backdrop, format);
+    for i in 0..count {
+        let color = if i as usize == index {
+            palette.accent
+        } else {
+            palette.remaining
+        };
+        Circle::new(
+            Point::new(left + (i * (diameter + DOT_GAP)) as i32, top),
+            diameter,
+        )
+        .into_styled(PrimitiveStyle::with_fill(color))
+        .draw(&mut layer)
+        .unwrap();
+    }
+    layer.finish();
+    clear_ring(display, layout, palette, backdrop, format);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracks(prefix: &str, count: usize) -> Vec<SimpleTrack> {
+        (0..count)
+            .map(|i| SimpleTrack {
+                name: format!("{prefix} {i}"),
+                artists: Vec::new(),
+                image_url: None,
+                small_url: None,
+                url: None,
+                duration: 200,
+            })
+            .collect()
+    }
+
+    fn top_tracks(short: usize, mid: usize, long: usize) -> TopTracks {
+        TopTracks::new(Data {
+            short_term_top: tracks("short", short),
+            mid_term_top: tracks("mid", mid),
+            long_term_top: tracks("long", long),
+        })
+    }
+
+    /// Range, rank and name of every track `advance` goes through, `count` times
+    fn advance(top_tracks: &mut TopTracks, count: usize) -> Vec<(TimeRange, usize, String)> {
+        let mut shown = Vec::new();
+        for _ in 0..count {
+            if let Some((range, rank, track)) = top_tracks.advance() {
+                shown.push((range, rank, track.name.clone()));
+            }
+        }
+        shown
+    }
+
+    #[test]
+    fn goes_through_every_range_and_wraps_around() {
+        let mut top_tracks = top_tracks(2, 1, 2);
+
+        assert_eq!(
+            advance(&mut top_tracks, 6),
+            [
+                (TimeRange::Short, 1, "short 0".to_string()),
+                (TimeRange::Short, 2, "short 1".to_string()),
+                (TimeRange::Mid, 1, "mid 0".to_string()),
+                (TimeRange::Long, 1, "long 0".to_string()),
+                (TimeRange::Long, 2, "long 1".to_string()),
+                (TimeRange::Short, 1, "short 0".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn shows_the_first_few_of_each_range() {
+        let mut top_tracks = top_tracks(SHOWN_PER_RANGE + 5, 0, 0);
+
+        let shown = advance(&mut top_tracks, SHOWN_PER_RANGE + 1);
+        assert_eq!(shown[SHOWN_PER_RANGE - 1].1, SHOWN_PER_RANGE);
+        assert_eq!(shown[SHOWN_PER_RANGE].1, 1);
+    }
+
+    #[test]
+    fn skips_empty_ranges() {
+        let mut top_tracks = top_tracks(0, 1, 0);
+
+        assert_eq!(
+            advance(&mut top_tracks, 2),
+            [
+                (TimeRange::Mid, 1, "mid 0".to_string()),
+                (TimeRange::Mid, 1, "mid 0".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn nothing_to_show_without_tracks() {
+        let mut top_tracks = top_tracks(0, 0, 0);
+
+        assert!(top_tracks.advance().is_none());
+        assert!(top_tracks.current().is_none());
+        // Keeps trying in case the next update brings tracks
+        assert!(top_tracks.update());
+        assert!(top_tracks.update());
+    }
+
+    #[test]
+    fn shows_each_track_for_30_seconds() {
+        let mut top_tracks = top_tracks(3, 0, 0);
+        let start = Instant::now();
+        let rank = |top_tracks: &TopTracks| top_tracks.current().map(|(_, rank, _)| rank);
+
+        // First track is shown right away
+        assert!(top_tracks.update_at(start));
+        assert_eq!(rank(&top_tracks), Some(1));
+
+        assert!(!top_tracks.update_at(start + Duration::from_secs(29)));
+        assert_eq!(rank(&top_tracks), Some(1));
+
+        assert!(top_tracks.update_at(start + SHOWN_FOR));
+        assert_eq!(rank(&top_tracks), Some(2));
+
+        // Counted from when it moved on, not from the start
+        assert!(!top_tracks.update_at(start + SHOWN_FOR + Duration::from_secs(29)));
+        assert!(top_tracks.update_at(start + SHOWN_FOR * 2));
+        assert_eq!(rank(&top_tracks), Some(3));
+    }
+}
@@ -0,0 +1,259 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use embedded_graphics::{
+    pixelcolor::{raw::RawU16, Rgb565},
+    prelude::*,
+    primitives::Rectangle,
+};
+
+use crate::{
+    draw_album_cover, palette::mix_rgb565, Backdrop, Framebuffer, Layout, Palette, PanelFormat,
+};
+
+/// How the previous album cover is replaced by the next one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Transition {
+    /// Replace instantly
+    #[default]
+    None,
+    Crossfade,
+    /// New cover pushes the old one out to the left
+    Slide,
+    /// New cover is revealed from left to right
+    Wipe,
+}
+
+/// Animates between two covers created by [`crate::rgb8_to_rgb565`], call [`CoverTransition::draw`] every tick
+#[derive(Debug, Clone)]
+pub struct CoverTransition {
+    transition: Transition,
+    from: Option<Arc<[u8]>>,
+    to: Option<Arc<[u8]>>,
+    duration: Duration,
+    started_at: Instant,
+}
+
+impl CoverTransition {
+    pub fn new(
+        transition: Transition,
+        from: Option<Arc<[u8]>>,
+        to: Option<Arc<[u8]>>,
+        duration: Duration,
+    ) -> Self {
+        Self {
+            transition,
+            from,
+            to,
+            duration,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Draws the frame for the current time, returns `true` once the last frame has been drawn
+    pub fn draw(
+        &self,
+        display: &mut Framebuffer,
+        layout: &Layout,
+        palette: Palette,
+        backdrop: Option<&Backdrop>,
+        format: PanelFormat,
+    ) -> bool {
+        let progress = if self.duration.is_zero() {
+            1.
+        } else {
+            (self.started_at.elapsed().as_secs_f32() / self.duration.as_secs_f32()).min(1.)
+        };
+
+        if progress >= 1. || self.transition == Transition::None {
+            draw_album_cover(
+                display,
+                layout,
+                self.to.as_deref(),
+                palette,
+                backdrop,
+                format,
+            );
+            return true;
+        }
+
+        let frame = self.frame(layout.cover, progress, palette.background, backdrop);
+        draw_album_cover(display, layout, Some(&frame), palette, backdrop, format);
+        false
+    }
+
+    /// Blends both covers drawn in `cover` at `progress` from 0 to 1, missing covers are shown as
+    /// whatever is behind them, `backdrop` or `background`
+    fn frame(
+        &self,
+        cover: Rectangle,
+        progress: f32,
+        background: Rgb565,
+        backdrop: Option<&Backdrop>,
+    ) -> Vec<u8> {
+        let side = cover.size.width;
+        let behind = |x: u32, y: u32| {
+            backdrop
+                .and_then(|backdrop| {
+                    let point = cover.top_left + Point::new(x as i32, y as i32);
+                    backdrop.row(point).first().copied()
+                })
+                .unwrap_or(background)
+        };
+        let pixel = |image: &Option<Arc<[u8]>>, x: u32, y: u32| -> Rgb565 {
+            let i = ((x + y * side) * 2) as usize;
+            match image.as_ref().and_then(|image| image.get(i..i + 2)) {
+                Some(bytes) => Rgb565::from(RawU16::new(u16::from_be_bytes([bytes[0], bytes[1]]))),
+                None => behind(x, y),
+            }
+        };
+        let edge = (progress * side as f32).round() as u32;
+
+        let mut frame = Vec::with_capacity((side * side * 2) as usize);
+        for y in 0..side {
+            for x in 0..side {
+                let color = match self.transition {
+                    Transition::None => pixel(&self.to, x, y),
+                    Transition::Crossfade => {
+                        mix_rgb565(pixel(&self.from, x, y), pixel(&self.to, x, y), progress)
+                    }
+                    Transition::Slide if x < side - edge => pixel(&self.from, x + edge, y),
+                    Transition::Slide => pixel(&self.to, x - (side - edge), y),
+                    Transition::Wipe if x < edge => pixel(&self.to, x, y),
+                    Transition::Wipe => pixel(&self.from, x, y),
+                };
+
+                frame.extend_from_slice(&color.into_storage().to_be_bytes());
+            }
+        }
+
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{ImageBuffer, Rgb};
+
+    use super::*;
+
+    const SIDE: u32 = 8;
+
+    /// Cover with the column of each pixel in red for `from` and in blue for `to`
+    fn cover(to: bool) -> Arc<[u8]> {
+        (0..SIDE * SIDE)
+            .flat_map(|i| {
+                let x = (i % SIDE) as u8;
+                let color = if to {
+                    Rgb565::new(0, 0, x)
+                } else {
+                    Rgb565::new(x, 0, 0)
+                };
+                color.into_storage().to_be_bytes()
+            })
+            .collect()
+    }
+
+    fn from_color(x: u32) -> Rgb565 {
+        Rgb565::new(x as u8, 0, 0)
+    }
+
+    fn to_color(x: u32) -> Rgb565 {
+        Rgb565::new(0, 0, x as u8)
+    }
+
+    /// Colors of the frame at `progress`, the cover drawn at the top left of the screen
+    fn frame(
+        transition: Transition,
+        from: Option<Arc<[u8]>>,
+        progress: f32,
+        backdrop: Option<&Backdrop>,
+    ) -> Vec<Rgb565> {
+        let transition = CoverTransition::new(transition, from, Some(cover(true)), Duration::ZERO);
+        let area = Rectangle::new(Point::zero(), Size::new(SIDE, SIDE));
+
+        transition
+            .frame(area, progress, Rgb565::BLACK, backdrop)
+            .chunks_exact(2)
+            .map(|bytes| Rgb565::from(RawU16::new(u16::from_be_bytes([bytes[0], bytes[1]]))))
+            .collect()
+    }
+
+    /// Asserts every pixel of `frame` is `expected` of its column
+    fn assert_columns(frame: &[Rgb565], expected: impl Fn(u32) -> Rgb565) {
+        for (i, color) in frame.iter().enumerate() {
+            let x = i as u32 % SIDE;
+            assert_eq!(*color, expected(x), "column {x}");
+        }
+    }
+
+    #[test]
+    fn every_transition_starts_at_the_old_cover_and_ends_at_the_new_one() {
+        for transition in [Transition::Crossfade, Transition::Slide, Transition::Wipe] {
+            assert_columns(&frame(transition, Some(cover(false)), 0., None), from_color);
+            assert_columns(&frame(transition, Some(cover(false)), 1., None), to_color);
+        }
+    }
+
+    #[test]
+    fn crossfade_halfway() {
+        let frame = frame(Transition::Crossfade, Some(cover(false)), 0.5, None);
+        assert_columns(&frame, |x| mix_rgb565(from_color(x), to_color(x), 0.5));
+    }
+
+    #[test]
+    fn slide_halfway() {
+        let frame = frame(Transition::Slide, Some(cover(false)), 0.5, None);
+
+        // Right half of the old cover is pushed left, the left half of the new one follows it
+        assert_columns(&frame, |x| {
+            if x < SIDE / 2 {
+                from_color(x + SIDE / 2)
+            } else {
+                to_color(x - SIDE / 2)
+            }
+        });
+    }
+
+    #[test]
+    fn wipe_halfway() {
+        let frame = frame(Transition::Wipe, Some(cover(false)), 0.5, None);
+
+        assert_columns(&frame, |x| {
+            if x < SIDE / 2 {
+                to_color(x)
+            } else {
+                from_color(x)
+            }
+        });
+    }
+
+    #[test]
+    fn missing_cover_is_the_background() {
+        let frame = frame(Transition::Wipe, None, 0.5, None);
+
+        assert_columns(&frame, |x| {
+            if x < SIDE / 2 {
+                to_color(x)
+            } else {
+                Rgb565::BLACK
+            }
+        });
+    }
+
+    #[test]
+    fn missing_cover_is_the_backdrop() {
+        let image = ImageBuffer::from_fn(16, 16, |x, y| Rgb([(x * 16) as u8, (y * 16) as u8, 0]));
+        let backdrop = Backdrop::new(&image, Size::new(SIDE, SIDE), Rgb565::WHITE);
+        let frame = frame(Transition::Crossfade, None, 0.5, Some(&backdrop));
+
+        for (i, color) in frame.iter().enumerate() {
+            let point = Point::new((i as u32 % SIDE) as i32, (i as u32 / SIDE) as i32);
+            let behind = backdrop.row(point)[0];
+            let x = point.x as u32;
+            assert_eq!(*color, mix_rgb565(behind, to_color(x), 0.5), "{point:?}");
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Last resort for text the fonts can't draw, replaces it with the closest ASCII
+
+/// Base letter of U+00C0 (À) to U+024F (ɏ), `_` where there is none
+const LATIN: &[u8; 400] = b"\
+    AAAAAA_CEEEEIIIIDNOOOOO_OUUUUY__aaaaaa_ceeeeiiiidnooooo_ouuuuy_y\
+    AaAaAaCcCcCcCcDdDdEeEeEeEeEeGgGgGgGgHhHhIiIiIiIiIi__JjKk_LlLlLlL\
+    lLlNnNnNnn__OoOoOo__RrRrRrSsSsSsSsTtTtTtUuUuUuUuUuUuWwYyYZzZzZzs\
+    ________________________________Oo_____________Uu_______________\
+    _____________AaIiOoUuUuUuUuUu_AaAa____GgKkOoOo__j___Gg__NnAa____\
+    AaAaEeEeIiIiOoOoRrRrUuUuSsTt__Hh______AaEeOoOoOoOoYy____________\
+    ________________";
+
+/// Base letter of U+1E00 (Ḁ) to U+1EFF (ỿ), mostly Vietnamese
+const LATIN_ADDITIONAL: &[u8; 256] = b"\
+    AaBbBbBbCcDdDdDdDdDdEeEeEeEeEeFfGgHhHhHhHhHhIiIiKkKkKkLlLlLlLlMm\
+    MmMmNnNnNnNnOoOoOoOoPpPpRrRrRrRrSsSsSsSsSsTtTtTtTtUuUuUuUuUuVvVv\
+    WwWwWwWwWwXxXxYyZzZzZzhtwy______AaAaAaAaAaAaAaAaAaAaAaAaEeEeEeEe\
+    EeEeEeEeIiIiOoOoOoOoOoOoOoOoOoOoOoOoUuUuUuUuUuUuUuYyYyYyYy______";
+
+/// U+0400 (Ѐ) to U+042F (Я), lowercase letters are offset from these
+const CYRILLIC: [&str; 48] = [
+    "E", "Yo", "Dj", "Gj", "Ye", "Dz", "I", "Yi", "J", "Lj", "Nj", "C", "Kj", "I", "U",
+    "Dz", //
+    "A", "B", "V", "G", "D", "E", "Zh", "Z", "I", "Y", "K", "L", "M", "N", "O", "P", //
+    "R", "S", "T", "U", "F", "Kh", "Ts", "Ch", "Sh", "Shch", "", "Y", "", "E", "Yu", "Ya",
+];
+
+/// U+0391 (Α) to U+03A9 (Ω), U+03A2 is unassigned but its lowercase form is the final ς
+const GREEK: [&str; 25] = [
+    "A", "V", "G", "D", "E", "Z", "I", "Th", "I", "K", "L", "M", "N", "X", "O", "P", "R", "S", "S",
+    "T", "Y", "F", "Ch", "Ps", "O",
+];
+
+/// Revised Romanization of Korean, indexed by the parts of a Hangul syllable
+const HANGUL_INITIALS: [&str; 19] = [
+    "g", "kk", "n", "d", "tt", "r", "m", "b", "pp", "s", "ss", "", "j", "jj", "ch", "k", "t", "p",
+    "h",
+];
+const HANGUL_VOWELS: [&str; 21] = [
+    "a", "ae", "ya", "yae", "eo", "e", "yeo", "ye", "o", "wa", "wae", "oe", "yo", "u", "wo", "we",
+    "wi", "yu", "eu", "ui", "i",
+];
+const HANGUL_FINALS: [&str; 28] = [
+    "", "k", "k", "k", "n", "n", "n", "t", "l", "k", "m", "l", "l", "l", "p", "l", "m", "p", "p",
+    "t", "t", "ng", "t", "t", "k", "t", "p", "t",
+];
+
+/// Strips diacritics and romanizes Cyrillic, Greek and Hangul, anything else is kept as is
+pub(crate) fn transliterate(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            c if c.is_ascii() => result.push(c),
+            // Combining diacritics, the base letter is drawn on its own
+            '\u{300}'..='\u{36f}' => {}
+            'Æ' => result.push_str("AE"),
+            'æ' => result.push_str("ae"),
+            'Œ' => result.push_str("OE"),
+            'œ' => result.push_str("oe"),
+            'Þ' => result.push_str("TH"),
+            'þ' => result.push_str("th"),
+            'Ŋ' => result.push_str("NG"),
+            'ŋ' => result.push_str("ng"),
+            'Ĳ' => result.push_str("IJ"),
+            'ĳ' => result.push_str("ij"),
+            'ß' => result.push_str("ss"),
+            'ẞ' => result.push_str("SS"),
+            '\u{c0}'..='\u{24f}' if LATIN[c as usize - 0xc0] != b'_' => {
+                result.push(LATIN[c as usize - 0xc0] as char)
+            }
+            '\u{1e00}'..='\u{1eff}' if LATIN_ADDITIONAL[c as usize - 0x1e00] != b'_' => {
+                result.push(LATIN_ADDITIONAL[c as usize - 0x1e00] as char)
+            }
+            '\u{400}'..='\u{42f}' => result.push_str(CYRILLIC[c as usize - 0x400]),
+            '\u{430}'..='\u{44f}' => result.push_str(&CYRILLIC[c as usize - 0x420].to_lowercase()),
+            '\u{450}'..='\u{45f}' => result.push_str(&CYRILLIC[c as usize - 0x450].to_lowercase()),
+            'Ґ' => result.push('G'),
+            'ґ' => result.push('g'),
+            '\u{386}'..='\u{3ce}' => {
+                let (base, lowercase) = match c {
+                    'Ά' => ('Α', false),
+                    'Έ' => ('Ε', false),
+                    'Ή' => ('Η', false),
+                    'Ί' | 'Ϊ' => ('Ι', false),
+                    'Ό' => ('Ο', false),
+                    'Ύ' | 'Ϋ' => ('Υ', false),
+                    'Ώ' => ('Ω', false),
+                    'ά' => ('Α', true),
+                    'έ' => ('Ε', true),
+                    'ή' => ('Η', true),
+                    'ί' | 'ϊ' | 'ΐ' => ('Ι', true),
+                    'ό' => ('Ο', true),
+                    'ύ' | 'ϋ' | 'ΰ' => ('Υ', true),
+                    'ώ' => ('Ω', true),
+                    '\u{391}'..='\u{3a9}' => (c, false),
+                    '\u{3b1}'..='\u{3c9}' => (char::from_u32(c as u32 - 0x20).unwrap(), true),
+                    _ => {
+                        result.push(c);
+                        continue;
+                    }
+                };
+
+                let latin = GREEK[base as usize - 0x391];
+                if lowercase {
+                    result.push_str(&latin.to_lowercase());
+                } else {
+                    result.push_str(latin);
+                }
+            }
+            '\u{ac00}'..='\u{d7a3}' => {
+                // Syllables are laid out by initial, then vowel, then final consonant
+                let syllable = c as usize - 0xac00;
+                result.push_str(HANGUL_INITIALS[syllable / (21 * 28)]);
+                result.push_str(HANGUL_VOWELS[syllable % (21 * 28) / 28]);
+                result.push_str(HANGUL_FINALS[syllable % 28]);
+            }
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn romanizes_hangul_syllables() {
+        assert_eq!(transliterate("한글"), "hangeul");
+        assert_eq!(transliterate("서울"), "seoul");
+        assert_eq!(transliterate("안녕하세요"), "annyeonghaseyo");
+        // First and last syllable of the block
+        assert_eq!(transliterate("가"), "ga");
+        assert_eq!(transliterate("힣"), "hit");
+    }
+
+    #[test]
+    fn romanizes_cyrillic() {
+        assert_eq!(transliterate("Москва"), "Moskva");
+        assert_eq!(transliterate("Щука"), "Shchuka");
+        assert_eq!(transliterate("ёжик"), "yozhik");
+        assert_eq!(transliterate("Київ"), "Kiyiv");
+        // Hard and soft signs are dropped
+        assert_eq!(transliterate("Объект"), "Obekt");
+        assert_eq!(transliterate("Ґанок"), "Ganok");
+    }
+
+    #[test]
+    fn romanizes_greek() {
+        assert_eq!(transliterate("Αθήνα"), "Athina");
+        assert_eq!(transliterate("Σοφία"), "Sofia");
+        // Final sigma
+        assert_eq!(transliterate("Ελλάς"), "Ellas");
+        assert_eq!(transliterate("ΨΥΧΉ"), "PsYChI");
+    }
+
+    #[test]
+    fn strips_diacritics() {
+        assert_eq!(transliterate("Beyoncé"), "Beyonce");
+        assert_eq!(transliterate("Ærø"), "AEro");
+        assert_eq!(transliterate("Straße"), "Strasse");
+        assert_eq!(transliterate("Phở"), "Pho");
+        assert_eq!(transliterate("Łódź"), "Lodz");
+        // Combining accent after the base letter
+        assert_eq!(transliterate("Ame\u{301}lie"), "Amelie");
+    }
+
+    #[test]
+    fn keeps_what_it_cant_map() {
+        for text in ["Plain ASCII", "東京", "ｱ", "🎵", "3×4", "·"] {
+            assert_eq!(transliterate(text), text);
+        }
+        assert_eq!(transliterate("Дом 東京"), "Dom 東京");
+    }
+}
@@ -0,0 +1,119 @@
+use std::time::{Duration, Instant};
+
+use embedded_graphics::{
+    mono_font::MonoTextStyle,
+    pixelcolor::Rgb565,
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+    text::{Alignment, Baseline, Text, TextStyleBuilder},
+};
+
+use crate::{Framebuffer, Layout, Palette, PanelFormat, Theme};
+
+/// Space around everything inside the overlay
+const INSET: u32 = 4;
+
+/// Volume bar and percentage shown over the middle of the cover for a moment after the volume changed
+///
+/// Whatever is below is saved from the [`Framebuffer`] before drawing and put back by
+/// [`VolumeOverlay::restore`], so nothing has to be redrawn once it's gone.
+#[derive(Debug, Clone)]
+pub struct VolumeOverlay {
+    volume: u32,
+    duration: Duration,
+    shown_at: Instant,
+    area: Rectangle,
+    /// Pixels below the overlay, saved when it's first drawn
+    below: Option<Vec<Rgb565>>,
+}
+
+impl VolumeOverlay {
+    /// Shows `volume` in percent for `duration`, call [`VolumeOverlay::draw`] to draw it
+    pub fn new(volume: u32, duration: Duration, layout: &Layout, theme: &Theme) -> Self {
+        let cover = layout.cover;
+        let width = cover.size.width.saturating_sub(INSET * 2);
+        let height = theme.text_size.time_font().character_size.height + INSET * 2;
+
+        Self {
+            volume,
+            duration,
+            shown_at: Instant::now(),
+            area: Rectangle::with_center(cover.center(), Size::new(width, height)),
+            below: None,
+        }
+    }
+
+    /// Shows another volume for the full duration again
+    pub fn set_volume(&mut self, volume: u32) {
+        self.volume = volume;
+        self.shown_at = Instant::now();
+    }
+
+    /// Whatever is below was drawn again, so it's saved again next time instead of the old pixels
+    pub fn below_changed(&mut self) {
+        self.below = None;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.shown_at.elapsed() >= self.duration
+    }
+
+    pub fn draw(
+        &mut self,
+        display: &mut Framebuffer,
+        theme: &Theme,
+        palette: Palette,
+        format: PanelFormat,
+    ) {
+        if self.below.is_none() {
+            self.below = Some(display.save(&self.area));
+        }
+
+        let font = theme.text_size.time_font();
+        let size = self.area.size;
+        let text_width = "100%".len() as u32 * font.character_size.width;
+        let bar_width = size.width.saturating_sub(INSET * 3 + text_width);
+        let filled = bar_width * self.volume.min(100) / 100;
+        let thickness = theme.spacing.thickness;
+        let top_left = self.area.top_left;
+        let bar_top = top_left.y + (size.height.saturating_sub(thickness) / 2) as i32;
+        let bar_left = top_left.x + INSET as i32;
+
+        let mut layer = display.layer(self.area, palette.background, None, format);
+        self.area
+            .into_styled(PrimitiveStyle::with_stroke(palette.remaining, 1))
+            .draw(&mut layer)
+            .unwrap();
+        Rectangle::new(Point::new(bar_left, bar_top), Size::new(filled, thickness))
+            .into_styled(PrimitiveStyle::with_fill(palette.accent))
+            .draw(&mut layer)
+            .unwrap();
+        Rectangle::new(
+            Point::new(bar_left + filled as i32, bar_top),
+            Size::new(bar_width - filled, thickness),
+        )
+        .into_styled(PrimitiveStyle::with_fill(palette.remaining))
+        .draw(&mut layer)
+        .unwrap();
+        Text::with_text_style(
+            &format!("{}%", self.volume),
+            top_left + Point::new(size.width.saturating_sub(INSET) as i32, INSET as i32),
+            MonoTextStyle::new(font, palette.text),
+            TextStyleBuilder::new()
+                .alignment(Alignment::Right)
+                .baseline(Baseline::Top)
+                .build(),
+        )
+        .draw(&mut layer)
+        .unwrap();
+
+        layer.finish();
+    }
+
+    /// Puts back whatever was below the overlay
+    pub fn restore(self, display: &mut Framebuffer) {
+        if let Some(below) = &self.below {
+            display.restore(&self.area, below);
+        }
+    }
+}
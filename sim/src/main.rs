@@ -1,37 +1,173 @@
 use std::{
-    io::{BufReader, Cursor},
-    sync::{mpsc, Arc},
+    io::{BufReader, Cursor, Read},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        mpsc, Arc,
+    },
     time::{Duration, Instant},
 };
 
-use common::{Playing, SimpleArtist, SimpleTrack};
+use common::{Context, Data, LastPlayed, Lyrics, Playing, SimpleArtist, SimpleTrack};
 use embedded_graphics_simulator::{
-    BinaryColorTheme, OutputSettingsBuilder, SimulatorDisplay, Window,
+    sdl2::Keycode, BinaryColorTheme, OutputSettingsBuilder, SimulatorDisplay, Window,
 };
 
 use embedded_graphics::{pixelcolor::Rgb565, prelude::*};
-use graphics::IMAGE_WIDTH;
-use image::{DynamicImage, GenericImageView};
+use graphics::{
+    Artwork, Backdrop, CountingTarget, CoverTransition, Dither, Framebuffer, Layout, LyricsView,
+    MarqueeMode, MarqueeStyle, Orientation, Palette, PanelFormat, ProgressStyle, Theme, TimeLabels,
+    TopTracks, TrackText, Transition, VolumeOverlay,
+};
+use image::{DynamicImage, GenericImageView, RgbImage};
 use ureq::Request;
 
+const API_URL: &str = "https://6q7btxffqgoyulwyg4jktyayzu0kvcyf.lambda-url.us-east-1.on.aws";
+/// How long the volume stays on screen after it changed, same as on the device
+const VOLUME_OVERLAY_DURATION: Duration = Duration::from_secs(2);
+/// How long the top tracks are kept before they are fetched again
+const TOP_TRACKS_REFRESH: Duration = Duration::from_secs(60 * 60);
+
 #[derive(Debug, Clone)]
 enum Message {
-    /// Sent to update currently playing song, with image if its different, bool indicates if it updated
-    UpdateSong(Option<Playing>, Option<Arc<[u8]>>, bool),
+    /// Sent to update currently playing song, bool indicates if it updated
+    UpdateSong(Option<Playing>, bool),
+    /// Sent once after playback stopped, shown until something plays again
+    LastPlayed(LastPlayed),
+    /// Sent while nothing is playing whenever the cached top tracks are out of date
+    TopTracks(Data),
+    /// Sent once the artwork for the named track is ready
+    UpdateArtwork(String, Artwork),
     UpdateProgress(u32),
     /// Sent when it is time to scroll text, whatever one is ready
     ScrollText,
 }
 
+/// What is shown while nothing is playing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IdleScreen {
+    NotPlaying,
+    LastPlayed,
+    TopTracks,
+}
+
+/// Asks the artwork worker to fetch and convert a track's cover
+struct ArtworkRequest {
+    /// Request is cancelled once this no longer matches the shared generation
+    generation: u32,
+    name: String,
+    url: Option<String>,
+    /// Album it is played from, every track of it gets the same placeholder
+    album: Option<String>,
+}
+
 fn main() -> color_eyre::Result<()> {
     color_eyre::install()?;
-    let mut display: SimulatorDisplay<Rgb565> = SimulatorDisplay::new(Size::new(128, 160));
+    // Put the cover left of the text with `ORIENTATION=landscape ./sim.sh`
+    let orientation = match std::env::var("ORIENTATION").as_deref() {
+        Ok("landscape") => Orientation::Landscape,
+        _ => Orientation::Portrait,
+    };
+    // Try other panels with `SCREEN_SIZE=240x320 ./sim.sh`, the window is turned to match the orientation
+    let screen_size = std::env::var("SCREEN_SIZE")
+        .ok()
+        .and_then(|size| parse_size(&size))
+        .unwrap_or(Size::new(128, 160));
+    let screen_size = match orientation {
+        Orientation::Portrait => screen_size,
+        Orientation::Landscape => Size::new(
+            screen_size.width.max(screen_size.height),
+            screen_size.width.min(screen_size.height),
+        ),
+    };
+    // Pick a theme with `THEME=dark|light|high-contrast ./sim.sh`, or load one with `THEME=theme.json`
+    let theme = match std::env::var("THEME") {
+        Ok(theme) => match Theme::by_name(&theme) {
+            Some(theme) => theme,
+            None => serde_json::from_str(&std::fs::read_to_string(theme)?)?,
+        },
+        Err(_) => Theme::default(),
+    };
+    // Try other progress styles with `PROGRESS=line|segments|ring|waveform ./sim.sh`
+    let theme = Theme {
+        progress_style: match std::env::var("PROGRESS").as_deref() {
+            Ok("bar") => ProgressStyle::Bar,
+            Ok("line") => ProgressStyle::Line,
+            Ok("segments") => ProgressStyle::Segments,
+            Ok("ring") => ProgressStyle::Ring,
+            Ok("waveform") => ProgressStyle::Waveform,
+            _ => theme.progress_style,
+        },
+        ..theme
+    };
+    // Render a sample track at every supported size with `SNAPSHOTS=dir ./sim.sh` to look at,
+    // `cargo test -p graphics` compares the default theme with the references in `graphics/fixtures`
+    if let Ok(dir) = std::env::var("SNAPSHOTS") {
+        return save_snapshots(dir.as_ref(), &theme);
+    }
+
+    let simulator_display: SimulatorDisplay<Rgb565> = SimulatorDisplay::new(screen_size);
+    let mut screen = CountingTarget::new(simulator_display);
+    // Simulator starts out black, same as the real display after clearing it
+    let mut display = Framebuffer::new(screen.bounding_box().size, Rgb565::new(0, 0, 0));
+    let layout = Layout::new(display.bounding_box(), orientation, &theme);
 
     let output_settings = OutputSettingsBuilder::new()
         .theme(BinaryColorTheme::Default)
         .max_fps(60)
         .build();
 
+    // Pick dithering with `DITHER=none|ordered|floyd-steinberg ./sim.sh`
+    let dither = match std::env::var("DITHER").as_deref() {
+        Ok("none") => Dither::None,
+        Ok("ordered") => Dither::Ordered,
+        Ok("floyd-steinberg") => Dither::FloydSteinberg,
+        _ => Dither::default(),
+    };
+    // Preview cover transitions with `TRANSITION=crossfade|slide|wipe TRANSITION_MS=400 ./sim.sh`
+    let transition = match std::env::var("TRANSITION").as_deref() {
+        Ok("crossfade") => Transition::Crossfade,
+        Ok("slide") => Transition::Slide,
+        Ok("wipe") => Transition::Wipe,
+        _ => Transition::None,
+    };
+    let transition_duration = Duration::from_millis(
+        std::env::var("TRANSITION_MS")
+            .ok()
+            .and_then(|ms| ms.parse().ok())
+            .unwrap_or(400),
+    );
+    // Try scrolling styles with `MARQUEE=loop|ping-pong|pause MARQUEE_SPEED=75 ./sim.sh`
+    let marquee_style = MarqueeStyle {
+        mode: match std::env::var("MARQUEE").as_deref() {
+            Ok("ping-pong") => MarqueeMode::PingPong,
+            Ok("pause") => MarqueeMode::PauseAtEnds,
+            _ => MarqueeMode::Loop,
+        },
+        speed: std::env::var("MARQUEE_SPEED")
+            .ok()
+            .and_then(|speed| speed.parse().ok())
+            .unwrap_or(75.),
+        ..Default::default()
+    };
+    // Print how many pixels each frame sends with `PIXEL_STATS=1 ./sim.sh`
+    let print_stats = std::env::var("PIXEL_STATS").is_ok_and(|var| var == "1");
+    // Show the blurred cover behind everything with `BACKDROP=1 ./sim.sh`
+    let use_backdrop = std::env::var("BACKDROP").is_ok_and(|var| var == "1");
+    // Show the name of the playback device with `DEVICE_NAME=1 ./sim.sh`
+    let show_device_name = std::env::var("DEVICE_NAME").is_ok_and(|var| var == "1");
+    // Go through the top tracks while nothing is playing with `IDLE=top-tracks ./sim.sh`, `T`
+    // switches back and forth like the button on the device
+    let top_tracks_idle = std::env::var("IDLE").is_ok_and(|var| var == "top-tracks");
+    // Show synced lyrics in place of the cover with `LYRICS=song.lrc ./sim.sh`, the same ones for
+    // every track
+    let lyrics = std::env::var("LYRICS")
+        .ok()
+        .map(std::fs::read_to_string)
+        .transpose()?
+        .map(|lrc| Lyrics::parse(&lrc));
+    // Simulator shows colors as they are drawn
+    let format = PanelFormat::default();
+
     let (sender, receiver) = mpsc::channel::<Message>();
 
     std::thread::spawn({
@@ -42,74 +178,110 @@ fn main() -> color_eyre::Result<()> {
         }
     });
 
+    // Bumped on every track change so the artwork worker can drop stale work
+    let artwork_generation = Arc::new(AtomicU32::new(0));
+    let (artwork_sender, artwork_receiver) = mpsc::channel::<ArtworkRequest>();
+
+    std::thread::spawn::<_, color_eyre::Result<()>>({
+        let sender = sender.clone();
+        let artwork_generation = artwork_generation.clone();
+        move || {
+            for mut request in artwork_receiver.iter() {
+                // Only the newest request matters
+                while let Ok(newer) = artwork_receiver.try_recv() {
+                    request = newer;
+                }
+
+                let is_cancelled =
+                    || artwork_generation.load(Ordering::Relaxed) != request.generation;
+                if is_cancelled() {
+                    continue;
+                }
+
+                // No cover, generate one so the track is still recognizable
+                let placeholder = || {
+                    graphics::placeholder_cover(
+                        &request.name,
+                        request.album.as_deref(),
+                        layout.cover.size.width,
+                    )
+                };
+                let rgb8_image = match &request.url {
+                    Some(url) => match get_image(url, layout.cover.size.width, &is_cancelled) {
+                        Ok(image) => image,
+                        Err(err) => {
+                            println!("Getting the cover of {} failed: {err}", request.name);
+                            Some(placeholder())
+                        }
+                    },
+                    None => Some(placeholder()),
+                };
+
+                // Cancelled, a newer track needs its cover
+                let Some(rgb8_image) = rgb8_image.filter(|_| !is_cancelled()) else {
+                    continue;
+                };
+
+                let artwork = Artwork::new(&rgb8_image, dither);
+                let artwork = if use_backdrop {
+                    artwork.with_backdrop(&rgb8_image, layout.screen.size, &theme)
+                } else {
+                    artwork
+                };
+
+                if !is_cancelled() {
+                    sender
+                        .send(Message::UpdateArtwork(request.name, artwork))
+                        .unwrap();
+                }
+            }
+
+            Ok(())
+        }
+    });
+
+    // Covers of whatever is shown while nothing is playing are asked for by the main loop
+    let idle_artwork_sender = artwork_sender.clone();
+    let idle_artwork_generation = artwork_generation.clone();
+
     std::thread::spawn::<_, color_eyre::Result<()>>(move || {
         let mut last_song = None::<String>;
-        let mut image_cache = None::<Arc<[u8]>>;
+        // Looked up once every time playback stops
+        let mut fetched_last_played = false;
+        let mut top_tracks_fetched_at = None::<Instant>;
 
         loop {
-            let playing = ureq::get(
-                "https://6q7btxffqgoyulwyg4jktyayzu0kvcyf.lambda-url.us-east-1.on.aws/playing",
-            )
-            .call()?
-            .into_json::<Option<Playing>>()
-            .ok()
-            .and_then(|p| p);
+            let playing = ureq::get(&format!("{API_URL}/playing"))
+                .call()?
+                .into_json::<Option<Playing>>()
+                .ok()
+                .and_then(|p| p);
 
             if let Some(playing) = playing {
-                match &playing.playing.image_url {
-                    Some(url)
-                        if image_cache.is_none()
-                            || !last_song
-                                .as_ref()
-                                .is_some_and(|last| &playing.playing.name == last) =>
-                    {
-                        let res = ureq::get(url).call()?;
-                        let length = res.header("content-length").unwrap().parse::<usize>()?;
-
-                        let image_type = res
-                            .header("content-type")
-                            .unwrap()
-                            .split_once("/")
-                            .unwrap()
-                            .1
-                            .to_string();
-
-                        let mut buf = Vec::with_capacity(length);
-                        res.into_reader().read_to_end(&mut buf)?;
-
-                        let image = match image_type.as_str() {
-                            "png" => DynamicImage::from_decoder(
-                                image::codecs::png::PngDecoder::new(Cursor::new(&buf)).unwrap(),
-                            ),
-                            "jpeg" | "jpg" => DynamicImage::from_decoder(
-                                image::codecs::jpeg::JpegDecoder::new(Cursor::new(&buf)).unwrap(),
-                            ),
-                            _ => panic!("Unsupported image type: {image_type}"),
-                        }?;
-
-                        let rgb8_image = image
-                            .resize(
-                                IMAGE_WIDTH,
-                                IMAGE_WIDTH,
-                                image::imageops::FilterType::Triangle,
-                            )
-                            .into_rgb8();
-
-                        image_cache = Some(Arc::from(graphics::rgb8_to_rgb565(&rgb8_image)))
-                    }
-                    _ => {}
-                };
-
+                fetched_last_played = false;
                 let changed = !last_song
                     .as_ref()
                     .is_some_and(|s| s == &playing.playing.name);
                 last_song = Some(playing.playing.name.clone());
+
+                if changed {
+                    // Cancels whatever the worker is doing for the last track
+                    let generation = artwork_generation.fetch_add(1, Ordering::Relaxed) + 1;
+                    artwork_sender.send(ArtworkRequest {
+                        generation,
+                        name: playing.playing.name.clone(),
+                        url: playing.playing.image_url.clone(),
+                        album: playing
+                            .context
+                            .as_ref()
+                            .and_then(Context::album_name)
+                            .map(str::to_string),
+                    })?;
+                }
+
+                // Text is shown right away, the cover follows with `Message::UpdateArtwork`
                 sender
-                    .send(Message::UpdateSong(
-                        Some(playing),
-                        image_cache.clone(),
-                        changed,
-                    ))
+                    .send(Message::UpdateSong(Some(playing), changed))
                     .unwrap();
 
                 for i in 1..=5 {
@@ -117,8 +289,32 @@ fn main() -> color_eyre::Result<()> {
                     sender.send(Message::UpdateProgress(i)).unwrap();
                 }
             } else {
+                if last_song.is_some() {
+                    artwork_generation.fetch_add(1, Ordering::Relaxed);
+                }
+                if !fetched_last_played {
+                    fetched_last_played = true;
+                    // "Not Playing" is only shown if this fails
+                    let last_played = ureq::get(&format!("{API_URL}/last-played"))
+                        .call()
+                        .ok()
+                        .and_then(|res| res.into_json::<LastPlayed>().ok());
+                    if let Some(last_played) = last_played {
+                        sender.send(Message::LastPlayed(last_played)).unwrap();
+                    }
+                }
+                if top_tracks_fetched_at.is_none_or(|at| at.elapsed() >= TOP_TRACKS_REFRESH) {
+                    top_tracks_fetched_at = Some(Instant::now());
+                    let data = ureq::get(&format!("{API_URL}/top-tracks"))
+                        .call()
+                        .ok()
+                        .and_then(|res| res.into_json::<Data>().ok());
+                    if let Some(data) = data {
+                        sender.send(Message::TopTracks(data)).unwrap();
+                    }
+                }
                 sender
-                    .send(Message::UpdateSong(None, None, last_song.is_some()))
+                    .send(Message::UpdateSong(None, last_song.is_some()))
                     .unwrap();
                 last_song = None;
                 std::thread::sleep(Duration::from_secs(5));
@@ -127,98 +323,764 @@ fn main() -> color_eyre::Result<()> {
     });
 
     let mut window = Window::new("Layout example", &output_settings);
-    window.update(&display);
+    window.update(screen.inner());
 
-    let mut shifting_title = true;
-    let mut title_shift = 0;
-    let mut composer_shift = 0;
+    let mut track_text = None::<TrackText>;
+    let mut time_labels = TimeLabels::new();
     let mut curr_playing = None::<Playing>;
-    let mut changed_at = Instant::now();
+    let mut curr_last_played = None::<LastPlayed>;
+    let mut top_tracks = None::<TopTracks>;
+    let mut show_top_tracks = top_tracks_idle;
+    let mut idle_screen = None::<IdleScreen>;
+    // Set on every poll while nothing is playing
+    let mut idle_update = false;
+    let mut curr_palette = theme.palette;
+    let mut curr_backdrop = None::<Backdrop>;
+    let mut curr_cover = None::<Arc<[u8]>>;
+    let mut cover_transition = None::<CoverTransition>;
+    let mut volume_overlay = None::<VolumeOverlay>;
+    let mut lyrics_view = None::<LyricsView>;
+    let mut progress_offset = 0;
 
     loop {
         match receiver.try_recv() {
             Ok(message) => match message {
-                Message::UpdateSong(playing, image, changed) => {
+                Message::UpdateSong(playing, changed) => {
                     if let Some(playing) = playing {
+                        curr_last_played = None;
+                        // Rank of a top track is drawn over its cover
+                        if idle_screen.take() == Some(IdleScreen::TopTracks) {
+                            graphics::draw_album_cover(
+                                &mut display,
+                                &layout,
+                                curr_cover.as_deref(),
+                                curr_palette,
+                                curr_backdrop.as_ref(),
+                                format,
+                            );
+                        }
                         if changed {
-                            // Only redraw image and name on new song
-                            shifting_title = true;
-                            title_shift = 0;
-                            composer_shift = 0;
-                            changed_at = Instant::now();
+                            // Only redraw name on new song, the old cover stays until the new one is ready
+                            let text = track_text.insert(TrackText::new(
+                                &playing,
+                                marquee_style,
+                                &layout,
+                                &theme,
+                            ));
 
-                            graphics::draw_album_cover(&mut display, image.as_deref());
+                            let had_lyrics = lyrics_view.take().is_some();
+                            if curr_cover.is_none() || had_lyrics {
+                                // Clear whatever was shown while nothing was playing
+                                graphics::draw_album_cover(
+                                    &mut display,
+                                    &layout,
+                                    curr_cover.as_deref(),
+                                    curr_palette,
+                                    curr_backdrop.as_ref(),
+                                    format,
+                                );
+                                if let Some(overlay) = &mut volume_overlay {
+                                    overlay.below_changed();
+                                }
+                            }
                             graphics::draw_current_name_and_artist(
                                 &mut display,
-                                &playing,
-                                &mut title_shift,
-                                &mut composer_shift,
+                                &layout,
+                                text,
+                                curr_palette,
+                                curr_backdrop.as_ref(),
+                                format,
                             );
+                            lyrics_view = lyrics
+                                .clone()
+                                .filter(|lyrics| !lyrics.lines.is_empty())
+                                .map(|lyrics| LyricsView::new(lyrics, &layout, &theme));
+                        }
+
+                        // Paused or resumed since the last poll, the cover is faded while paused
+                        let was_paused = curr_playing.as_ref().is_some_and(|curr| !curr.is_playing);
+                        if was_paused == playing.is_playing && lyrics_view.is_none() {
+                            cover_transition = None;
+                            if playing.is_playing {
+                                graphics::draw_album_cover(
+                                    &mut display,
+                                    &layout,
+                                    curr_cover.as_deref(),
+                                    curr_palette,
+                                    curr_backdrop.as_ref(),
+                                    format,
+                                );
+                            } else {
+                                graphics::draw_paused_cover(
+                                    &mut display,
+                                    &layout,
+                                    curr_cover.as_deref(),
+                                    curr_palette,
+                                    curr_backdrop.as_ref(),
+                                    format,
+                                );
+                            }
+                            if let Some(overlay) = &mut volume_overlay {
+                                overlay.below_changed();
+                            }
                         }
 
+                        // Shuffle, repeat and the device can change without the track changing
+                        graphics::draw_status(
+                            &mut display,
+                            &layout,
+                            &theme,
+                            &playing,
+                            show_device_name,
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            format,
+                        );
                         graphics::draw_current_progress(
                             &mut display,
+                            &layout,
+                            &theme,
                             playing.progress_secs,
                             playing.playing.duration,
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            format,
                         );
+                        time_labels.draw(
+                            &mut display,
+                            &layout,
+                            &theme,
+                            playing.progress_secs,
+                            playing.playing.duration,
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            format,
+                        );
+                        progress_offset = 0;
+                        if let Some(view) = &mut lyrics_view {
+                            if view.draw(
+                                &mut display,
+                                &layout,
+                                &theme,
+                                playing.progress_secs * 1000,
+                                curr_palette,
+                                curr_backdrop.as_ref(),
+                                format,
+                            ) {
+                                if let Some(overlay) = &mut volume_overlay {
+                                    overlay.below_changed();
+                                }
+                            }
+                        }
+
+                        // Changes on the same device are shown for a moment
+                        let volume = curr_playing
+                            .as_ref()
+                            .filter(|curr| curr.device.name == playing.device.name)
+                            .and_then(|curr| {
+                                playing
+                                    .device
+                                    .volume_percent
+                                    .filter(|volume| curr.device.volume_percent != Some(*volume))
+                            });
+                        if let Some(volume) = volume {
+                            match &mut volume_overlay {
+                                Some(overlay) => overlay.set_volume(volume),
+                                None => {
+                                    volume_overlay = Some(VolumeOverlay::new(
+                                        volume,
+                                        VOLUME_OVERLAY_DURATION,
+                                        &layout,
+                                        &theme,
+                                    ))
+                                }
+                            }
+                        }
+                        if let Some(overlay) = &mut volume_overlay {
+                            overlay.draw(&mut display, &theme, curr_palette, format);
+                        }
                         curr_playing = Some(playing);
                     } else {
-                        curr_playing = None;
-                        graphics::draw_no_song(&mut display);
+                        idle_update = true;
                     }
                 }
-                Message::UpdateProgress(offset) => {
-                    if let Some(playing) = &curr_playing {
-                        graphics::draw_current_progress(
+                Message::LastPlayed(last_played) => {
+                    curr_last_played = Some(last_played);
+                    // Drawn from scratch by the idle poll which follows
+                    idle_screen = None;
+                }
+                Message::TopTracks(data) => {
+                    top_tracks = Some(TopTracks::new(data));
+                }
+                Message::UpdateArtwork(name, artwork) => {
+                    // Artwork may arrive after the track already changed again
+                    let idle_name = match idle_screen {
+                        Some(IdleScreen::LastPlayed) => curr_last_played
+                            .as_ref()
+                            .map(|last_played| &last_played.track.name),
+                        Some(IdleScreen::TopTracks) => top_tracks
+                            .as_ref()
+                            .and_then(TopTracks::current)
+                            .map(|(_, _, track)| &track.name),
+                        _ => None,
+                    };
+                    if let Some(screen) = idle_screen.filter(|_| idle_name == Some(&name)) {
+                        curr_palette = theme.track_palette(Some(artwork.palette));
+                        curr_backdrop = artwork.backdrop;
+                        graphics::draw_album_cover(
                             &mut display,
-                            playing.progress_secs + offset,
-                            playing.playing.duration,
+                            &layout,
+                            Some(&artwork.cover),
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            format,
                         );
-                    }
-                }
-                // Only scroll text after 3 seconds since
-                Message::ScrollText if changed_at.elapsed() > Duration::from_secs(3) => {
-                    if let Some(playing) = &curr_playing {
-                        if shifting_title {
-                            title_shift += 1;
+                        curr_cover = Some(artwork.cover);
+
+                        if let Some(text) = &track_text {
                             graphics::draw_current_name_and_artist(
                                 &mut display,
-                                playing,
-                                &mut title_shift,
-                                &mut composer_shift,
+                                &layout,
+                                text,
+                                curr_palette,
+                                curr_backdrop.as_ref(),
+                                format,
                             );
+                        }
+                        draw_idle_labels(
+                            &mut display,
+                            &layout,
+                            &theme,
+                            screen,
+                            curr_last_played.as_ref(),
+                            top_tracks.as_ref(),
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            format,
+                        );
+                    } else if let Some(playing) = curr_playing
+                        .as_ref()
+                        .filter(|playing| playing.playing.name == name)
+                    {
+                        curr_palette = theme.track_palette(Some(artwork.palette));
+                        curr_backdrop = artwork.backdrop;
+                        time_labels.clear();
 
-                            if title_shift == 0 {
-                                shifting_title = false;
+                        if let Some(view) = &mut lyrics_view {
+                            // Cover is kept for when the lyrics are gone
+                            view.clear();
+                            view.draw(
+                                &mut display,
+                                &layout,
+                                &theme,
+                                (playing.progress_secs + progress_offset) * 1000,
+                                curr_palette,
+                                curr_backdrop.as_ref(),
+                                format,
+                            );
+                        } else if playing.is_playing {
+                            let transition = CoverTransition::new(
+                                transition,
+                                curr_cover.take(),
+                                Some(artwork.cover.clone()),
+                                transition_duration,
+                            );
+                            if !transition.draw(
+                                &mut display,
+                                &layout,
+                                curr_palette,
+                                curr_backdrop.as_ref(),
+                                format,
+                            ) {
+                                cover_transition = Some(transition);
                             }
                         } else {
-                            composer_shift += 1;
-                            graphics::draw_current_name_and_artist(
+                            // No animation while paused
+                            cover_transition = None;
+                            graphics::draw_paused_cover(
                                 &mut display,
-                                playing,
-                                &mut title_shift,
-                                &mut composer_shift,
+                                &layout,
+                                Some(&artwork.cover),
+                                curr_palette,
+                                curr_backdrop.as_ref(),
+                                format,
                             );
+                        }
+                        if let Some(overlay) = &mut volume_overlay {
+                            overlay.below_changed();
+                            overlay.draw(&mut display, &theme, curr_palette, format);
+                        }
+                        curr_cover = Some(artwork.cover);
 
-                            if composer_shift == 0 {
-                                shifting_title = true;
+                        // Colors come with the cover, so redraw everything else with them
+                        graphics::draw_status(
+                            &mut display,
+                            &layout,
+                            &theme,
+                            playing,
+                            show_device_name,
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            format,
+                        );
+                        if let Some(text) = &track_text {
+                            graphics::draw_current_name_and_artist(
+                                &mut display,
+                                &layout,
+                                text,
+                                curr_palette,
+                                curr_backdrop.as_ref(),
+                                format,
+                            );
+                        }
+                        graphics::draw_current_progress(
+                            &mut display,
+                            &layout,
+                            &theme,
+                            playing.progress_secs + progress_offset,
+                            playing.playing.duration,
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            format,
+                        );
+                        time_labels.draw(
+                            &mut display,
+                            &layout,
+                            &theme,
+                            playing.progress_secs + progress_offset,
+                            playing.playing.duration,
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            format,
+                        );
+                    }
+                }
+                Message::UpdateProgress(offset) => {
+                    // Time stands still while paused
+                    if let Some(playing) =
+                        curr_playing.as_ref().filter(|playing| playing.is_playing)
+                    {
+                        progress_offset = offset;
+                        if let Some(view) = &mut lyrics_view {
+                            if view.draw(
+                                &mut display,
+                                &layout,
+                                &theme,
+                                (playing.progress_secs + offset) * 1000,
+                                curr_palette,
+                                curr_backdrop.as_ref(),
+                                format,
+                            ) {
+                                if let Some(overlay) = &mut volume_overlay {
+                                    overlay.below_changed();
+                                    overlay.draw(&mut display, &theme, curr_palette, format);
+                                }
                             }
                         }
+                        graphics::draw_current_progress(
+                            &mut display,
+                            &layout,
+                            &theme,
+                            playing.progress_secs + offset,
+                            playing.playing.duration,
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            format,
+                        );
+                        time_labels.draw(
+                            &mut display,
+                            &layout,
+                            &theme,
+                            playing.progress_secs + offset,
+                            playing.playing.duration,
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            format,
+                        );
+                    }
+                }
+                // Text waits for the cover to finish animating
+                Message::ScrollText if cover_transition.is_some() => {
+                    if let Some(transition) = &cover_transition {
+                        if transition.draw(
+                            &mut display,
+                            &layout,
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            format,
+                        ) {
+                            cover_transition = None;
+                        }
+                    }
+                    // Stays on top of every frame
+                    if let Some(overlay) = &mut volume_overlay {
+                        overlay.below_changed();
+                        overlay.draw(&mut display, &theme, curr_palette, format);
+                    }
+                }
+                Message::ScrollText => {
+                    if let Some(text) = &mut track_text {
+                        // Only redraw once the text actually moved
+                        if text.update() {
+                            graphics::draw_current_name_and_artist(
+                                &mut display,
+                                &layout,
+                                text,
+                                curr_palette,
+                                curr_backdrop.as_ref(),
+                                format,
+                            );
+                        }
                     }
                 }
-                _ => {}
             },
             Err(_) => {
                 for event in window.events() {
                     match event {
                         embedded_graphics_simulator::SimulatorEvent::Quit => std::process::exit(0),
+                        embedded_graphics_simulator::SimulatorEvent::KeyDown {
+                            keycode: Keycode::T,
+                            ..
+                        } => {
+                            show_top_tracks = !show_top_tracks;
+                            // Shown right away, otherwise once nothing is playing
+                            idle_update = curr_playing.is_none();
+                        }
                         _ => {}
                     }
                 }
             }
         }
 
-        window.update(&display);
+        // Moves the carousel on or keeps "played … ago" current
+        if idle_update {
+            idle_update = false;
+            let mut advanced = false;
+            let top_track = match top_tracks.as_mut().filter(|_| show_top_tracks) {
+                Some(top_tracks) => {
+                    advanced = top_tracks.update();
+                    top_tracks.current()
+                }
+                None => None,
+            }
+            .map(|(_, _, track)| track.clone());
+            let screen = match (&top_track, &curr_last_played) {
+                (Some(_), _) => IdleScreen::TopTracks,
+                (None, Some(_)) => IdleScreen::LastPlayed,
+                (None, None) => IdleScreen::NotPlaying,
+            };
+
+            // Every top track is another one, the last played track stays
+            if idle_screen != Some(screen) || (screen == IdleScreen::TopTracks && advanced) {
+                idle_screen = Some(screen);
+                curr_playing = None;
+                track_text = None;
+                time_labels.clear();
+                curr_palette = theme.palette;
+                curr_backdrop = None;
+                curr_cover = None;
+                cover_transition = None;
+                volume_overlay = None;
+                lyrics_view = None;
+
+                let track = match &top_track {
+                    Some(track) => Some((track, None)),
+                    None => curr_last_played
+                        .as_ref()
+                        .map(|last_played| (&last_played.track, last_played.context.as_ref())),
+                };
+                match track {
+                    Some((track, context)) => {
+                        // Cover follows with `Message::UpdateArtwork` like for a playing track
+                        let generation =
+                            idle_artwork_generation.fetch_add(1, Ordering::Relaxed) + 1;
+                        idle_artwork_sender.send(ArtworkRequest {
+                            generation,
+                            name: track.name.clone(),
+                            url: track.image_url.clone(),
+                            album: context.and_then(Context::album_name).map(str::to_string),
+                        })?;
+
+                        let text = track_text.insert(TrackText::from_track(
+                            track,
+                            context,
+                            marquee_style,
+                            &layout,
+                            &theme,
+                        ));
+                        graphics::draw_album_cover(
+                            &mut display,
+                            &layout,
+                            None,
+                            curr_palette,
+                            None,
+                            format,
+                        );
+                        graphics::draw_current_name_and_artist(
+                            &mut display,
+                            &layout,
+                            text,
+                            curr_palette,
+                            None,
+                            format,
+                        );
+                    }
+                    None => graphics::draw_no_song(&mut display, &theme, format),
+                }
+            }
+            draw_idle_labels(
+                &mut display,
+                &layout,
+                &theme,
+                screen,
+                curr_last_played.as_ref(),
+                top_tracks.as_ref(),
+                curr_palette,
+                curr_backdrop.as_ref(),
+                format,
+            );
+        }
+
+        // Same as the firmware, changed rows are sent once per message
+        if volume_overlay
+            .as_ref()
+            .is_some_and(VolumeOverlay::is_finished)
+        {
+            volume_overlay.take().unwrap().restore(&mut display);
+        }
+
+        display.flush_to(&mut screen).unwrap();
+        window.update(screen.inner());
+
+        let stats = screen.take_stats();
+        if print_stats && stats.pixels != 0 {
+            println!(
+                "Pushed {} pixels in {} transfers",
+                stats.pixels, stats.transfers
+            );
+        }
     }
     Ok(())
 }
+
+/// Whatever goes around the track while nothing is playing, drawn again whenever its colors change
+#[allow(clippy::too_many_arguments)]
+fn draw_idle_labels(
+    display: &mut Framebuffer,
+    layout: &Layout,
+    theme: &Theme,
+    screen: IdleScreen,
+    last_played: Option<&LastPlayed>,
+    top_tracks: Option<&TopTracks>,
+    palette: Palette,
+    backdrop: Option<&Backdrop>,
+    format: PanelFormat,
+) {
+    match (screen, last_played, top_tracks) {
+        (IdleScreen::LastPlayed, Some(last_played), _) => graphics::draw_last_played(
+            display,
+            layout,
+            theme,
+            last_played.secs_ago(),
+            palette,
+            backdrop,
+            format,
+        ),
+        (IdleScreen::TopTracks, _, Some(top_tracks)) => graphics::draw_top_track(
+            display, layout, theme, top_tracks, palette, backdrop, format,
+        ),
+        _ => {}
+    }
+}
+
+/// Downloads and resizes a cover, giving up early if `is_cancelled` returns `true`
+fn get_image(
+    url: &str,
+    side: u32,
+    is_cancelled: &impl Fn() -> bool,
+) -> color_eyre::Result<Option<RgbImage>> {
+    let res = ureq::get(url).call()?;
+    let length = res
+        .header("content-length")
+        .and_then(|length| length.parse::<usize>().ok())
+        .unwrap_or_default();
+
+    let image_type = res
+        .header("content-type")
+        .and_then(|content_type| content_type.split_once('/'))
+        .map(|(_, image_type)| image_type.to_string())
+        .unwrap_or_default();
+
+    let mut buf = Vec::with_capacity(length);
+    let mut reader = res.into_reader();
+    let mut chunk = [0u8; 4 * 1024];
+    loop {
+        if is_cancelled() {
+            return Ok(None);
+        }
+
+        match reader.read(&mut chunk)? {
+            0 => break,
+            read => buf.extend_from_slice(&chunk[..read]),
+        }
+    }
+
+    let image = match image_type.as_str() {
+        "png" => {
+            DynamicImage::from_decoder(image::codecs::png::PngDecoder::new(Cursor::new(&buf))?)
+        }
+        "jpeg" | "jpg" => {
+            DynamicImage::from_decoder(image::codecs::jpeg::JpegDecoder::new(Cursor::new(&buf))?)
+        }
+        _ => color_eyre::eyre::bail!("unsupported image type {image_type:?}"),
+    }?;
+
+    Ok(Some(
+        image
+            .resize(side, side, image::imageops::FilterType::Triangle)
+            .into_rgb8(),
+    ))
+}
+
+/// Parses sizes like `240x320`
+fn parse_size(size: &str) -> Option<Size> {
+    let (width, height) = size.split_once('x')?;
+    Some(Size::new(width.parse().ok()?, height.parse().ok()?))
+}
+
+/// Panels the layout has to work on
+const SNAPSHOT_SIZES: [(Size, Orientation); 7] = [
+    (Size::new(128, 128), Orientation::Portrait),
+    (Size::new(128, 160), Orientation::Portrait),
+    (Size::new(240, 240), Orientation::Portrait),
+    (Size::new(240, 320), Orientation::Portrait),
+    (Size::new(320, 480), Orientation::Portrait),
+    (Size::new(160, 128), Orientation::Landscape),
+    (Size::new(320, 240), Orientation::Landscape),
+];
+
+/// Draws a sample track at each of [`SNAPSHOT_SIZES`] and saves them as `{width}x{height}.png` in `dir`
+fn save_snapshots(dir: &std::path::Path, theme: &Theme) -> color_eyre::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let playing: Playing =
+        serde_json::from_str(include_str!("../../graphics/fixtures/snapshot.json"))?;
+
+    for (size, orientation) in SNAPSHOT_SIZES {
+        let path = dir.join(format!("{}x{}.png", size.width, size.height));
+        save_snapshot(
+            &path,
+            &playing,
+            size,
+            orientation,
+            theme,
+            playing.progress_secs,
+            playing.playing.duration,
+        )?;
+    }
+
+    // Every progress style at the start, halfway, the end and before the duration is known
+    let duration = playing.playing.duration;
+    for progress_style in ProgressStyle::ALL {
+        let theme = Theme {
+            progress_style,
+            ..*theme
+        };
+        for (name, progress_secs, duration) in [
+            ("0", 0, duration),
+            ("50", duration / 2, duration),
+            ("100", duration, duration),
+            ("unknown", 0, 0),
+        ] {
+            let path = dir.join(format!(
+                "progress-{}-{name}.png",
+                format!("{progress_style:?}").to_lowercase()
+            ));
+            save_snapshot(
+                &path,
+                &playing,
+                Size::new(128, 160),
+                Orientation::Portrait,
+                &theme,
+                progress_secs,
+                duration,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn save_snapshot(
+    path: &std::path::Path,
+    playing: &Playing,
+    size: Size,
+    orientation: Orientation,
+    theme: &Theme,
+    progress_secs: u32,
+    duration: u32,
+) -> color_eyre::Result<()> {
+    let output_settings = OutputSettingsBuilder::new().build();
+    let format = PanelFormat::default();
+
+    let mut screen: SimulatorDisplay<Rgb565> = SimulatorDisplay::new(size);
+    let mut display = Framebuffer::new(size, Rgb565::BLACK);
+    let layout = Layout::new(display.bounding_box(), orientation, theme);
+
+    let cover = graphics::placeholder_cover(
+        &playing.playing.name,
+        playing.context.as_ref().and_then(Context::album_name),
+        layout.cover.size.width,
+    );
+    let artwork = Artwork::new(&cover, Dither::default());
+    let text = TrackText::new(playing, MarqueeStyle::default(), &layout, theme);
+    let palette = theme.track_palette(Some(artwork.palette));
+    let mut time_labels = TimeLabels::new();
+
+    graphics::draw_status(
+        &mut display,
+        &layout,
+        theme,
+        playing,
+        true,
+        palette,
+        None,
+        format,
+    );
+    graphics::draw_album_cover(
+        &mut display,
+        &layout,
+        Some(&artwork.cover),
+        palette,
+        None,
+        format,
+    );
+    graphics::draw_current_name_and_artist(&mut display, &layout, &text, palette, None, format);
+    graphics::draw_current_progress(
+        &mut display,
+        &layout,
+        theme,
+        progress_secs,
+        duration,
+        palette,
+        None,
+        format,
+    );
+    time_labels.draw(
+        &mut display,
+        &layout,
+        theme,
+        progress_secs,
+        duration,
+        palette,
+        None,
+        format,
+    );
+
+    display.flush_to(&mut screen).unwrap();
+    screen
+        .to_rgb_output_image(&output_settings)
+        .save_png(path)?;
+    println!("Saved {}", path.display());
+
+    Ok(())
+}
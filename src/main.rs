@@ -1,45 +1,166 @@
+mod panel;
 mod wifi;
 
 use std::{
+    fmt,
     io::BufReader,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
 
-use common::Playing;
-use embedded_graphics::{pixelcolor::Rgb565, prelude::*};
+use common::{Context, Data, LastPlayed, Lyrics, Playing};
+use embedded_graphics::{pixelcolor::Rgb565, prelude::*, primitives::Rectangle};
 use esp_idf_svc::{
     eventloop::EspSystemEventLoop,
     hal::{
         delay::Delay,
-        gpio::PinDriver,
+        gpio::{PinDriver, Pull},
         prelude::*,
-        spi::{config::MODE_3, SpiDeviceDriver, SpiDriverConfig},
+        spi::{config::MODE_3, Dma, SpiDeviceDriver, SpiDriverConfig},
     },
     http::client::{Configuration, EspHttpConnection},
-    nvs::EspDefaultNvsPartition,
-    sys::{esp_crt_bundle_attach, esp_get_free_heap_size},
+    nvs::{EspDefaultNvsPartition, EspNvs},
+    sntp::EspSntp,
+    sys::{esp_crt_bundle_attach, esp_get_free_heap_size, EspError},
     wifi::{BlockingWifi, EspWifi},
 };
-use graphics::IMAGE_WIDTH;
-use image::{DynamicImage, ImageBuffer};
+use graphics::{
+    Artwork, Backdrop, ByteOrder, ColorOrder, CoverTransition, Dither, Framebuffer, Layout,
+    LyricsView, MarqueeMode, MarqueeStyle, Orientation, Palette, PanelFormat, Theme, TimeLabels,
+    TopTracks, TrackText, Transition, VolumeOverlay,
+};
+use image::{DynamicImage, ImageBuffer, RgbImage};
+use serde::de::DeserializeOwned;
 
-use crate::wifi::init_enterprise;
+use crate::{panel::Panel, wifi::init_enterprise};
 
 const USE_WPA_ENTERPRISE: bool = true;
 const WPA_ENTERPRISE_SSID: &'static str = "eduroam";
 const WPA_SSID: &'static str = "GraceHouse";
 const WPA_PASSWORD: &'static str = include_str!("../wpa-pass.txt");
+const API_URL: &str = "https://6q7btxffqgoyulwyg4jktyayzu0kvcyf.lambda-url.us-east-1.on.aws";
+/// Dithering used when converting album covers for the display
+const DITHER: Dither = Dither::FloydSteinberg;
+/// How the panel expects pixels, change this instead of the driver flags for other ST7735 variants
+const PANEL_FORMAT: PanelFormat = PanelFormat::new(ColorOrder::Rgb, ByteOrder::Big, false);
+/// Fill the screen with a blurred version of the cover instead of a solid color
+const USE_BLURRED_BACKDROP: bool = false;
+/// Animation used when the album cover changes
+const COVER_TRANSITION: Transition = Transition::Crossfade;
+const COVER_TRANSITION_DURATION: Duration = Duration::from_millis(400);
+/// How long the volume stays on screen after it changed
+const VOLUME_OVERLAY_DURATION: Duration = Duration::from_secs(2);
+/// How the title and artists scroll when they don't fit
+const MARQUEE_STYLE: MarqueeStyle = MarqueeStyle {
+    mode: MarqueeMode::Loop,
+    speed: 75.,
+    pause: Duration::from_secs(3),
+};
+/// Show the name of the playback device next to its icon
+const SHOW_DEVICE_NAME: bool = false;
+/// How long the top tracks are kept before they are fetched again
+const TOP_TRACKS_REFRESH: Duration = Duration::from_secs(60 * 60);
+/// Size of the ST7735 panel on the board, upright
+const SCREEN_WIDTH: u32 = 128;
+const SCREEN_HEIGHT: u32 = 160;
+/// NVS namespace of settings which can change without reflashing
+const SETTINGS_NAMESPACE: &str = "display";
+/// Flush the framebuffer from its own thread, so the next frame can be drawn while the last one is sent
+const DOUBLE_BUFFER: bool = true;
+/// Bytes in a full frame, the most a single flush sends
+const FRAME_BYTES: usize = (SCREEN_WIDTH * SCREEN_HEIGHT * 2) as usize;
+/// Largest DMA transfer the SPI driver accepts, it has to be a multiple of 4
+const DMA_TRANSFER_BYTES: usize = 4096 * 8;
 
 #[derive(Debug, Clone)]
 enum Message {
-    /// Sent to update currently playing song, with image if its different, bool indicates if it updated
-    UpdateSong(Option<Playing>, Option<Arc<[u8]>>, bool),
+    /// Sent to update currently playing song, bool indicates if it updated
+    UpdateSong(Option<Playing>, bool),
+    /// Sent once after playback stopped, shown until something plays again
+    LastPlayed(LastPlayed),
+    /// Sent while nothing is playing whenever the cached top tracks are out of date
+    TopTracks(Data),
+    /// Sent when the button is pressed, switches between the last played and the top tracks
+    ToggleTopTracks,
+    /// Sent once the artwork for the named track is ready
+    UpdateArtwork(String, Artwork),
+    /// Sent after the named track started if it has synced lyrics
+    UpdateLyrics(String, Lyrics),
     UpdateProgress,
     /// Sent when it is time to scroll text, whatever one is ready
     ScrollText,
 }
 
+/// What is shown while nothing is playing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IdleScreen {
+    NotPlaying,
+    LastPlayed,
+    TopTracks,
+}
+
+/// Asks the artwork worker to fetch and convert a track's cover
+struct ArtworkRequest {
+    /// Request is cancelled once this no longer matches the shared generation
+    generation: u32,
+    name: String,
+    url: Option<String>,
+    /// Album it is played from, every track of it gets the same placeholder
+    album: Option<String>,
+}
+
+/// Why [`get_json`] or [`get_image`] failed
+#[derive(Debug)]
+enum RequestError {
+    /// Connecting, sending or reading failed
+    Connection(EspError),
+    /// Connection closed before the whole response was read
+    Truncated,
+    /// Response doesn't fit in the buffer, `None` if it has no `content-length`
+    TooLarge(Option<usize>),
+    Status(u16),
+    Json(serde_json::Error),
+    /// Cover isn't an RGB or grayscale JPEG, holds what it is instead
+    UnsupportedImage(String),
+    Jpeg(jpeg_decoder::Error),
+}
+
+impl RequestError {
+    /// Whether the connection is left in the middle of a response and can't be used again
+    fn breaks_connection(&self) -> bool {
+        matches!(
+            self,
+            RequestError::Connection(_) | RequestError::Truncated | RequestError::TooLarge(None)
+        )
+    }
+}
+
+impl From<EspError> for RequestError {
+    fn from(err: EspError) -> Self {
+        RequestError::Connection(err)
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RequestError::Connection(err) => write!(f, "connection failed: {err}"),
+            RequestError::Truncated => write!(f, "response ended early"),
+            RequestError::TooLarge(Some(length)) => {
+                write!(f, "response is too large: {length} bytes")
+            }
+            RequestError::TooLarge(None) => write!(f, "response without a length is too large"),
+            RequestError::Status(status) => write!(f, "bad status {status}"),
+            RequestError::Json(err) => write!(f, "invalid response: {err}"),
+            RequestError::UnsupportedImage(kind) => write!(f, "unsupported image: {kind}"),
+            RequestError::Jpeg(err) => write!(f, "invalid jpeg: {err}"),
+        }
+    }
+}
+
 fn main() {
     // It is necessary to call this function once. Otherwise some patches to the runtime
     // implemented by esp-idf-sys might not link properly. See https://github.com/esp-rs/esp-idf-template/issues/71
@@ -66,7 +187,8 @@ fn main() {
         sdo,
         Some(sdi),
         Some(cs),
-        &SpiDriverConfig::default(),
+        // The driver splits anything larger, so a full frame goes out in two DMA transfers
+        &SpiDriverConfig::new().dma(Dma::Auto(DMA_TRANSFER_BYTES)),
         &config,
     )
     .unwrap();
@@ -74,15 +196,73 @@ fn main() {
     let dc = PinDriver::output(peripherals.pins.gpio17).unwrap();
     let rst = PinDriver::output(peripherals.pins.gpio18).unwrap();
 
+    let nvs = EspDefaultNvsPartition::take().unwrap();
+    let settings = EspNvs::new(nvs.clone(), SETTINGS_NAMESPACE, true).unwrap();
+    // Set `orientation` to 1 to turn the panel sideways, no reflashing needed
+    let orientation = match settings.get_u8("orientation").unwrap() {
+        Some(1) => Orientation::Landscape,
+        _ => Orientation::Portrait,
+    };
+    // Set `idle` to 1 to go through the top tracks instead of showing the last played track
+    let top_tracks_idle = settings.get_u8("idle").unwrap() == Some(1);
+    // Set `lyrics` to 1 to show synced lyrics in place of the cover when there are any
+    let show_lyrics = settings.get_u8("lyrics").unwrap() == Some(1);
+    // Either `dark`, `light`, `high-contrast` or a whole theme as JSON
+    let mut theme_buf = vec![0u8; 1024];
+    let theme = match settings.get_str("theme", &mut theme_buf).unwrap() {
+        Some(theme) => Theme::by_name(theme).unwrap_or_else(|| {
+            serde_json::from_str(theme).unwrap_or_else(|err| {
+                log::error!("Invalid theme in NVS: {err}");
+                Theme::default()
+            })
+        }),
+        None => Theme::default(),
+    };
+    let screen_size = match orientation {
+        Orientation::Portrait => Size::new(SCREEN_WIDTH, SCREEN_HEIGHT),
+        Orientation::Landscape => Size::new(SCREEN_HEIGHT, SCREEN_WIDTH),
+    };
+
     log::info!("Creating display dirver.");
-    let mut display = st7735_lcd::ST7735::new(spi_driver, dc, rst, true, false, 128, 160);
-    display.init(&mut Delay::new_default()).unwrap();
+    let mut panel = Panel::new(spi_driver, dc, rst, orientation);
 
     log::info!("Clearing Display...");
-    display.clear(Rgb565::new(0, 0, 0)).unwrap();
+    // Large enough to end up in PSRAM, everything is drawn here and sent to the panel after each message
+    let mut display = Framebuffer::new(screen_size, Rgb565::new(0, 0, 0));
+    let black = Rgb565::new(0, 0, 0).into_storage().to_be_bytes();
+    panel.flush(
+        display.bounding_box(),
+        &black.repeat(FRAME_BYTES / black.len()),
+    );
+
+    let layout = Layout::new(display.bounding_box(), orientation, &theme);
+
+    // With double buffering the flush thread owns the panel and buffers are passed back and forth
+    let (flush_sender, flush_receiver) = crossbeam_channel::bounded::<(Rectangle, Vec<u8>)>(1);
+    let (free_sender, free_receiver) = crossbeam_channel::bounded::<Vec<u8>>(2);
+    // Changed areas are copied out of the framebuffer row by row before they are sent
+    let mut flush_buf = Vec::with_capacity(if DOUBLE_BUFFER { 0 } else { FRAME_BYTES });
+    let mut panel = if DOUBLE_BUFFER {
+        for _ in 0..2 {
+            free_sender.send(Vec::with_capacity(FRAME_BYTES)).unwrap();
+        }
+
+        std::thread::Builder::new()
+            .stack_size(8 * 1024)
+            .spawn(move || {
+                for (area, bytes) in flush_receiver.iter() {
+                    panel.flush(area, &bytes);
+                    free_sender.send(bytes).unwrap();
+                }
+            })
+            .unwrap();
+
+        None
+    } else {
+        Some(panel)
+    };
 
     let sysloop = EspSystemEventLoop::take().unwrap();
-    let nvs = EspDefaultNvsPartition::take().unwrap();
     let mut wifi = BlockingWifi::wrap(
         EspWifi::new(peripherals.modem, sysloop.clone(), Some(nvs)).unwrap(),
         sysloop,
@@ -99,6 +279,9 @@ fn main() {
         );
     }
 
+    // Keeps the clock set, only needed to tell how long ago the last track was played
+    let _sntp = EspSntp::new_default().unwrap();
+
     const ALBUM_LENGTH: usize = 300;
     let (sender, receiver) = crossbeam_channel::bounded::<Message>(16);
 
@@ -124,62 +307,196 @@ fn main() {
         })
         .unwrap();
 
+    // BOOT button on the dev board, pulled low while pressed
+    let mut button = PinDriver::input(peripherals.pins.gpio0).unwrap();
+    button.set_pull(Pull::Up).unwrap();
+    std::thread::Builder::new()
+        .stack_size(4 * 1024)
+        .spawn({
+            let sender = sender.clone();
+            move || {
+                let mut was_pressed = false;
+                loop {
+                    // Also long enough for the contacts to settle
+                    Delay::new_default().delay_ms(20);
+                    let pressed = button.is_low();
+                    if pressed && !was_pressed {
+                        sender.send(Message::ToggleTopTracks).unwrap();
+                    }
+                    was_pressed = pressed;
+                }
+            }
+        })
+        .unwrap();
+
+    // Bumped on every track change so the artwork worker can drop stale work
+    let artwork_generation = Arc::new(AtomicU32::new(0));
+    let (artwork_sender, artwork_receiver) = crossbeam_channel::unbounded::<ArtworkRequest>();
+
+    std::thread::Builder::new()
+        .stack_size(64 * 1024)
+        .spawn({
+            let sender = sender.clone();
+            let artwork_generation = artwork_generation.clone();
+            move || {
+                let new_client = || {
+                    EspHttpConnection::new(&Configuration {
+                        buffer_size: Some(ALBUM_LENGTH * ALBUM_LENGTH),
+                        crt_bundle_attach: Some(esp_crt_bundle_attach),
+                        ..Default::default()
+                    })
+                    .unwrap()
+                };
+                let mut client = new_client();
+
+                let mut image_buf = vec![0u8; ALBUM_LENGTH * ALBUM_LENGTH];
+
+                for mut request in artwork_receiver.iter() {
+                    // Only the newest request matters
+                    while let Ok(newer) = artwork_receiver.try_recv() {
+                        request = newer;
+                    }
+
+                    let is_cancelled =
+                        || artwork_generation.load(Ordering::Relaxed) != request.generation;
+                    if is_cancelled() {
+                        continue;
+                    }
+
+                    // If no image_url, generate one so the track is still recognizable
+                    let placeholder = || {
+                        create_artwork(
+                            &graphics::placeholder_cover(
+                                &request.name,
+                                request.album.as_deref(),
+                                layout.cover.size.width,
+                            ),
+                            &layout,
+                            &theme,
+                            DITHER,
+                            USE_BLURRED_BACKDROP,
+                        )
+                    };
+                    let artwork = match &request.url {
+                        Some(url) => match get_image(
+                            url,
+                            &mut client,
+                            &mut image_buf,
+                            &layout,
+                            &theme,
+                            DITHER,
+                            USE_BLURRED_BACKDROP,
+                            &is_cancelled,
+                        ) {
+                            Ok(artwork) => artwork,
+                            Err(err) => {
+                                log::error!("Getting the cover of {} failed: {err}", request.name);
+                                if err.breaks_connection() {
+                                    client = new_client();
+                                }
+                                Some(placeholder())
+                            }
+                        },
+                        None => Some(placeholder()),
+                    };
+
+                    match artwork {
+                        Some(artwork) if !is_cancelled() => sender
+                            .send(Message::UpdateArtwork(request.name, artwork))
+                            .unwrap(),
+                        _ => {
+                            log::info!("Cancelled artwork for {}", request.name);
+                            // Connection may be in the middle of a response
+                            client = new_client();
+                        }
+                    }
+                }
+            }
+        })
+        .unwrap();
+
+    // Covers of whatever is shown while nothing is playing are asked for by the main loop
+    let idle_artwork_sender = artwork_sender.clone();
+    let idle_artwork_generation = artwork_generation.clone();
+
     std::thread::Builder::new()
         .stack_size(64 * 1024)
         .spawn(move || {
-            let mut client = EspHttpConnection::new(&Configuration {
-                buffer_size: Some(ALBUM_LENGTH * ALBUM_LENGTH),
-                crt_bundle_attach: Some(esp_crt_bundle_attach),
-                ..Default::default()
-            })
-            .unwrap();
+            let mut client = new_api_client();
 
             let mut res_buf = vec![0u8; 4 * 1024];
-            let mut image_buf = vec![0u8; ALBUM_LENGTH * ALBUM_LENGTH];
+            // Three lists of tracks or whole lyrics don't fit in the small one
+            let mut large_res_buf = vec![0u8; 64 * 1024];
 
             let mut last_song = None::<String>;
-            let mut image_cache = None::<Arc<[u8]>>;
+            // Looked up once every time playback stops
+            let mut fetched_last_played = false;
+            let mut top_tracks_fetched_at = None::<Instant>;
 
             loop {
-                let playing = get_playing(&mut client, &mut res_buf);
-
-                if let Err(_) = playing {
-                    Delay::new_default().delay_ms(5 * 1000);
-                    continue;
-                }
-
-                if let Some(playing) = playing.unwrap() {
-                    match &playing.playing.image_url {
-                        Some(url) => {
-                            if image_cache.is_none()
-                                || !last_song
-                                    .as_ref()
-                                    .is_some_and(|last| &playing.playing.name == last)
-                            {
-                                image_cache = Some(get_image(url, &mut client, &mut image_buf))
-                            }
-                        }
-                        _ => {
-                            // If no image_url, don't show image
-                            image_cache = None;
+                let playing =
+                    match get_json::<Option<Playing>>("/playing", &mut client, &mut res_buf) {
+                        Ok(playing) => playing,
+                        Err(err) => {
+                            // Keep showing whatever is on screen and try again
+                            request_failed("/playing", err, &mut client);
+                            Delay::new_default().delay_ms(5 * 1000);
+                            continue;
                         }
                     };
 
+                if let Some(playing) = playing {
+                    fetched_last_played = false;
                     let changed = !last_song
                         .as_ref()
                         .is_some_and(|s| s == &playing.playing.name);
                     last_song = Some(playing.playing.name.clone());
                     let progress = playing.progress_secs;
                     let duration = playing.playing.duration;
+                    let name = playing.playing.name.clone();
+                    let lyrics_id = playing
+                        .playing
+                        .id()
+                        .filter(|_| changed && show_lyrics)
+                        .map(str::to_string);
+
+                    if changed {
+                        // Cancels whatever the worker is doing for the last track
+                        let generation = artwork_generation.fetch_add(1, Ordering::Relaxed) + 1;
+                        artwork_sender
+                            .send(ArtworkRequest {
+                                generation,
+                                name: playing.playing.name.clone(),
+                                url: playing.playing.image_url.clone(),
+                                album: playing
+                                    .context
+                                    .as_ref()
+                                    .and_then(Context::album_name)
+                                    .map(str::to_string),
+                            })
+                            .unwrap();
+                    }
 
+                    // Text is shown right away, the cover follows with `Message::UpdateArtwork`
                     sender
-                        .send(Message::UpdateSong(
-                            Some(playing),
-                            image_cache.clone(),
-                            changed,
-                        ))
+                        .send(Message::UpdateSong(Some(playing), changed))
                         .unwrap();
 
+                    // Cover stays for tracks without synced lyrics
+                    if let Some(id) = lyrics_id {
+                        let path = format!("/lyrics/{id}");
+                        match get_json::<Option<String>>(&path, &mut client, &mut large_res_buf) {
+                            Ok(Some(lrc)) => {
+                                let lyrics = Lyrics::parse(&lrc);
+                                if !lyrics.lines.is_empty() {
+                                    sender.send(Message::UpdateLyrics(name, lyrics)).unwrap();
+                                }
+                            }
+                            Ok(None) => {}
+                            Err(err) => request_failed(&path, err, &mut client),
+                        }
+                    }
+
                     // Simulate progress between requesting next update
                     for i in 1..=5 {
                         Delay::new_default().delay_ms(1000);
@@ -190,8 +507,33 @@ fn main() {
                         }
                     }
                 } else {
+                    if last_song.is_some() {
+                        artwork_generation.fetch_add(1, Ordering::Relaxed);
+                    }
+                    if !fetched_last_played {
+                        fetched_last_played = true;
+                        // "Not Playing" is only shown if this fails
+                        match get_json::<LastPlayed>("/last-played", &mut client, &mut res_buf) {
+                            Ok(last_played) => {
+                                sender.send(Message::LastPlayed(last_played)).unwrap();
+                            }
+                            Err(err) => {
+                                request_failed("/last-played", err, &mut client);
+                                // Asked for again on the next poll
+                                fetched_last_played = false;
+                            }
+                        }
+                    }
+                    if top_tracks_fetched_at.map_or(true, |at| at.elapsed() >= TOP_TRACKS_REFRESH) {
+                        top_tracks_fetched_at = Some(Instant::now());
+                        match get_json::<Data>("/top-tracks", &mut client, &mut large_res_buf) {
+                            Ok(data) => sender.send(Message::TopTracks(data)).unwrap(),
+                            // Last ones stay until the next refresh
+                            Err(err) => request_failed("/top-tracks", err, &mut client),
+                        }
+                    }
                     sender
-                        .send(Message::UpdateSong(None, None, last_song.is_some()))
+                        .send(Message::UpdateSong(None, last_song.is_some()))
                         .unwrap();
                     last_song = None;
                     Delay::new_default().delay_ms(5 * 1000);
@@ -200,189 +542,771 @@ fn main() {
         })
         .unwrap();
 
-    let mut shifting_title = true;
-    let mut title_shift = 0;
-    let mut composer_shift = 0;
+    let mut track_text = None::<TrackText>;
+    let mut time_labels = TimeLabels::new();
     let mut curr_playing = None::<Playing>;
+    let mut curr_last_played = None::<LastPlayed>;
+    let mut top_tracks = None::<TopTracks>;
+    let mut show_top_tracks = top_tracks_idle;
+    let mut idle_screen = None::<IdleScreen>;
+    // Set on every poll while nothing is playing
+    let mut idle_update = false;
+    let mut curr_palette = theme.palette;
+    let mut curr_backdrop = None::<Backdrop>;
+    let mut curr_cover = None::<Arc<[u8]>>;
+    let mut cover_transition = None::<CoverTransition>;
+    let mut volume_overlay = None::<VolumeOverlay>;
+    let mut lyrics_view = None::<LyricsView>;
     let mut progress_offset = 0;
-    let mut scroll_ended_at = Instant::now();
 
     loop {
         match receiver.try_recv() {
             Ok(message) => match message {
-                Message::UpdateSong(playing, image, changed) => {
+                Message::UpdateSong(playing, changed) => {
                     if let Some(playing) = playing {
+                        curr_last_played = None;
+                        // Rank of a top track is drawn over its cover
+                        if idle_screen.take() == Some(IdleScreen::TopTracks) {
+                            graphics::draw_album_cover(
+                                &mut display,
+                                &layout,
+                                curr_cover.as_deref(),
+                                curr_palette,
+                                curr_backdrop.as_ref(),
+                                PANEL_FORMAT,
+                            );
+                        }
                         if changed {
-                            // Only redraw image and name on new song
-                            shifting_title = true;
-                            title_shift = 0;
-                            composer_shift = 0;
-                            scroll_ended_at = Instant::now();
+                            // Only redraw name on new song, the old cover stays until the new one is ready
+                            let text = track_text.insert(TrackText::new(
+                                &playing,
+                                MARQUEE_STYLE,
+                                &layout,
+                                &theme,
+                            ));
 
-                            graphics::draw_album_cover(&mut display, image.as_deref());
+                            // Lyrics of the next track follow with `Message::UpdateLyrics`
+                            let had_lyrics = lyrics_view.take().is_some();
+                            if curr_cover.is_none() || had_lyrics {
+                                // Clear whatever was shown while nothing was playing
+                                graphics::draw_album_cover(
+                                    &mut display,
+                                    &layout,
+                                    curr_cover.as_deref(),
+                                    curr_palette,
+                                    curr_backdrop.as_ref(),
+                                    PANEL_FORMAT,
+                                );
+                                if let Some(overlay) = &mut volume_overlay {
+                                    overlay.below_changed();
+                                }
+                            }
                             graphics::draw_current_name_and_artist(
                                 &mut display,
-                                &playing,
-                                &mut title_shift,
-                                &mut composer_shift,
+                                &layout,
+                                text,
+                                curr_palette,
+                                curr_backdrop.as_ref(),
+                                PANEL_FORMAT,
                             );
                         }
 
+                        // Paused or resumed since the last poll, the cover is faded while paused
+                        let was_paused = curr_playing.as_ref().is_some_and(|curr| !curr.is_playing);
+                        if was_paused == playing.is_playing && lyrics_view.is_none() {
+                            cover_transition = None;
+                            if playing.is_playing {
+                                graphics::draw_album_cover(
+                                    &mut display,
+                                    &layout,
+                                    curr_cover.as_deref(),
+                                    curr_palette,
+                                    curr_backdrop.as_ref(),
+                                    PANEL_FORMAT,
+                                );
+                            } else {
+                                graphics::draw_paused_cover(
+                                    &mut display,
+                                    &layout,
+                                    curr_cover.as_deref(),
+                                    curr_palette,
+                                    curr_backdrop.as_ref(),
+                                    PANEL_FORMAT,
+                                );
+                            }
+                            if let Some(overlay) = &mut volume_overlay {
+                                overlay.below_changed();
+                            }
+                        }
+
+                        // Shuffle, repeat and the device can change without the track changing
+                        graphics::draw_status(
+                            &mut display,
+                            &layout,
+                            &theme,
+                            &playing,
+                            SHOW_DEVICE_NAME,
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            PANEL_FORMAT,
+                        );
                         graphics::draw_current_progress(
                             &mut display,
+                            &layout,
+                            &theme,
+                            playing.progress_secs,
+                            playing.playing.duration,
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            PANEL_FORMAT,
+                        );
+                        time_labels.draw(
+                            &mut display,
+                            &layout,
+                            &theme,
                             playing.progress_secs,
                             playing.playing.duration,
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            PANEL_FORMAT,
                         );
                         progress_offset = 0;
+                        if let Some(view) = &mut lyrics_view {
+                            if view.draw(
+                                &mut display,
+                                &layout,
+                                &theme,
+                                playing.progress_secs * 1000,
+                                curr_palette,
+                                curr_backdrop.as_ref(),
+                                PANEL_FORMAT,
+                            ) {
+                                if let Some(overlay) = &mut volume_overlay {
+                                    overlay.below_changed();
+                                }
+                            }
+                        }
+
+                        // Changes on the same device are shown for a moment
+                        let volume = curr_playing
+                            .as_ref()
+                            .filter(|curr| curr.device.name == playing.device.name)
+                            .and_then(|curr| {
+                                playing
+                                    .device
+                                    .volume_percent
+                                    .filter(|volume| curr.device.volume_percent != Some(*volume))
+                            });
+                        if let Some(volume) = volume {
+                            match &mut volume_overlay {
+                                Some(overlay) => overlay.set_volume(volume),
+                                None => {
+                                    volume_overlay = Some(VolumeOverlay::new(
+                                        volume,
+                                        VOLUME_OVERLAY_DURATION,
+                                        &layout,
+                                        &theme,
+                                    ))
+                                }
+                            }
+                        }
+                        if let Some(overlay) = &mut volume_overlay {
+                            overlay.draw(&mut display, &theme, curr_palette, PANEL_FORMAT);
+                        }
                         curr_playing = Some(playing);
                     } else {
-                        curr_playing = None;
-                        graphics::draw_no_song(&mut display);
+                        idle_update = true;
                     }
                 }
-                Message::UpdateProgress => {
-                    if let Some(playing) = &curr_playing {
-                        progress_offset += 1;
-                        graphics::draw_current_progress(
+                Message::LastPlayed(last_played) => {
+                    curr_last_played = Some(last_played);
+                    // Drawn from scratch by the idle poll which follows
+                    idle_screen = None;
+                }
+                Message::TopTracks(data) => {
+                    top_tracks = Some(TopTracks::new(data));
+                }
+                Message::ToggleTopTracks => {
+                    show_top_tracks = !show_top_tracks;
+                    // Shown right away, otherwise once nothing is playing
+                    idle_update = curr_playing.is_none();
+                }
+                Message::UpdateArtwork(name, artwork) => {
+                    // Artwork may arrive after the track already changed again
+                    let idle_name = match idle_screen {
+                        Some(IdleScreen::LastPlayed) => curr_last_played
+                            .as_ref()
+                            .map(|last_played| &last_played.track.name),
+                        Some(IdleScreen::TopTracks) => top_tracks
+                            .as_ref()
+                            .and_then(TopTracks::current)
+                            .map(|(_, _, track)| &track.name),
+                        _ => None,
+                    };
+                    if let Some(screen) = idle_screen.filter(|_| idle_name == Some(&name)) {
+                        curr_palette = theme.track_palette(Some(artwork.palette));
+                        curr_backdrop = artwork.backdrop;
+                        graphics::draw_album_cover(
                             &mut display,
-                            playing.progress_secs + progress_offset,
-                            playing.playing.duration,
+                            &layout,
+                            Some(&artwork.cover),
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            PANEL_FORMAT,
                         );
-                    }
-                }
-                // Only scroll text after 3 seconds since
-                Message::ScrollText if scroll_ended_at.elapsed() > Duration::from_secs(3) => {
-                    if let Some(playing) = &curr_playing {
-                        if shifting_title {
-                            title_shift += 1;
+                        curr_cover = Some(artwork.cover);
+
+                        if let Some(text) = &track_text {
                             graphics::draw_current_name_and_artist(
                                 &mut display,
-                                playing,
-                                &mut title_shift,
-                                &mut composer_shift,
+                                &layout,
+                                text,
+                                curr_palette,
+                                curr_backdrop.as_ref(),
+                                PANEL_FORMAT,
                             );
+                        }
+                        draw_idle_labels(
+                            &mut display,
+                            &layout,
+                            &theme,
+                            screen,
+                            curr_last_played.as_ref(),
+                            top_tracks.as_ref(),
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                        );
+                    } else if let Some(playing) = curr_playing
+                        .as_ref()
+                        .filter(|playing| playing.playing.name == name)
+                    {
+                        curr_palette = theme.track_palette(Some(artwork.palette));
+                        curr_backdrop = artwork.backdrop;
+                        time_labels.clear();
 
-                            if title_shift == 0 {
-                                shifting_title = false;
-                                scroll_ended_at = Instant::now();
+                        if let Some(view) = &mut lyrics_view {
+                            // Cover is kept for when the lyrics are gone
+                            view.clear();
+                            view.draw(
+                                &mut display,
+                                &layout,
+                                &theme,
+                                (playing.progress_secs + progress_offset) * 1000,
+                                curr_palette,
+                                curr_backdrop.as_ref(),
+                                PANEL_FORMAT,
+                            );
+                        } else if playing.is_playing {
+                            let transition = CoverTransition::new(
+                                COVER_TRANSITION,
+                                curr_cover.take(),
+                                Some(artwork.cover.clone()),
+                                COVER_TRANSITION_DURATION,
+                            );
+                            if !transition.draw(
+                                &mut display,
+                                &layout,
+                                curr_palette,
+                                curr_backdrop.as_ref(),
+                                PANEL_FORMAT,
+                            ) {
+                                cover_transition = Some(transition);
                             }
                         } else {
-                            composer_shift += 1;
-                            graphics::draw_current_name_and_artist(
+                            // No animation while paused
+                            cover_transition = None;
+                            graphics::draw_paused_cover(
                                 &mut display,
-                                playing,
-                                &mut title_shift,
-                                &mut composer_shift,
+                                &layout,
+                                Some(&artwork.cover),
+                                curr_palette,
+                                curr_backdrop.as_ref(),
+                                PANEL_FORMAT,
                             );
+                        }
+                        if let Some(overlay) = &mut volume_overlay {
+                            overlay.below_changed();
+                            overlay.draw(&mut display, &theme, curr_palette, PANEL_FORMAT);
+                        }
+                        curr_cover = Some(artwork.cover);
 
-                            if composer_shift == 0 {
-                                shifting_title = true;
-                                scroll_ended_at = Instant::now();
+                        // Colors come with the cover, so redraw everything else with them
+                        graphics::draw_status(
+                            &mut display,
+                            &layout,
+                            &theme,
+                            playing,
+                            SHOW_DEVICE_NAME,
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            PANEL_FORMAT,
+                        );
+                        if let Some(text) = &track_text {
+                            graphics::draw_current_name_and_artist(
+                                &mut display,
+                                &layout,
+                                text,
+                                curr_palette,
+                                curr_backdrop.as_ref(),
+                                PANEL_FORMAT,
+                            );
+                        }
+                        graphics::draw_current_progress(
+                            &mut display,
+                            &layout,
+                            &theme,
+                            playing.progress_secs + progress_offset,
+                            playing.playing.duration,
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            PANEL_FORMAT,
+                        );
+                        time_labels.draw(
+                            &mut display,
+                            &layout,
+                            &theme,
+                            playing.progress_secs + progress_offset,
+                            playing.playing.duration,
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            PANEL_FORMAT,
+                        );
+                    }
+                }
+                Message::UpdateLyrics(name, lyrics) => {
+                    if let Some(playing) = curr_playing
+                        .as_ref()
+                        .filter(|playing| playing.playing.name == name)
+                    {
+                        cover_transition = None;
+                        let view = lyrics_view.insert(LyricsView::new(lyrics, &layout, &theme));
+                        view.draw(
+                            &mut display,
+                            &layout,
+                            &theme,
+                            (playing.progress_secs + progress_offset) * 1000,
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            PANEL_FORMAT,
+                        );
+                        if let Some(overlay) = &mut volume_overlay {
+                            overlay.below_changed();
+                            overlay.draw(&mut display, &theme, curr_palette, PANEL_FORMAT);
+                        }
+                    }
+                }
+                Message::UpdateProgress => {
+                    // Time stands still while paused
+                    if let Some(playing) =
+                        curr_playing.as_ref().filter(|playing| playing.is_playing)
+                    {
+                        progress_offset += 1;
+                        if let Some(view) = &mut lyrics_view {
+                            if view.draw(
+                                &mut display,
+                                &layout,
+                                &theme,
+                                (playing.progress_secs + progress_offset) * 1000,
+                                curr_palette,
+                                curr_backdrop.as_ref(),
+                                PANEL_FORMAT,
+                            ) {
+                                if let Some(overlay) = &mut volume_overlay {
+                                    overlay.below_changed();
+                                    overlay.draw(&mut display, &theme, curr_palette, PANEL_FORMAT);
+                                }
                             }
                         }
+                        graphics::draw_current_progress(
+                            &mut display,
+                            &layout,
+                            &theme,
+                            playing.progress_secs + progress_offset,
+                            playing.playing.duration,
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            PANEL_FORMAT,
+                        );
+                        time_labels.draw(
+                            &mut display,
+                            &layout,
+                            &theme,
+                            playing.progress_secs + progress_offset,
+                            playing.playing.duration,
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            PANEL_FORMAT,
+                        );
+                    }
+                }
+                // Text waits for the cover to finish animating
+                Message::ScrollText if cover_transition.is_some() => {
+                    if let Some(transition) = &cover_transition {
+                        if transition.draw(
+                            &mut display,
+                            &layout,
+                            curr_palette,
+                            curr_backdrop.as_ref(),
+                            PANEL_FORMAT,
+                        ) {
+                            cover_transition = None;
+                        }
+                    }
+                    // Stays on top of every frame
+                    if let Some(overlay) = &mut volume_overlay {
+                        overlay.below_changed();
+                        overlay.draw(&mut display, &theme, curr_palette, PANEL_FORMAT);
+                    }
+                }
+                Message::ScrollText => {
+                    if let Some(text) = &mut track_text {
+                        // Only redraw once the text actually moved
+                        if text.update() {
+                            graphics::draw_current_name_and_artist(
+                                &mut display,
+                                &layout,
+                                text,
+                                curr_palette,
+                                curr_backdrop.as_ref(),
+                                PANEL_FORMAT,
+                            );
+                        }
                     }
                 }
-                _ => {}
             },
             // No events to process
             Err(_) => {}
         }
 
+        // Moves the carousel on or keeps "played … ago" current
+        if idle_update {
+            idle_update = false;
+            let mut advanced = false;
+            let top_track = match top_tracks.as_mut().filter(|_| show_top_tracks) {
+                Some(top_tracks) => {
+                    advanced = top_tracks.update();
+                    top_tracks.current()
+                }
+                None => None,
+            }
+            .map(|(_, _, track)| track.clone());
+            let screen = match (&top_track, &curr_last_played) {
+                (Some(_), _) => IdleScreen::TopTracks,
+                (None, Some(_)) => IdleScreen::LastPlayed,
+                (None, None) => IdleScreen::NotPlaying,
+            };
+
+            // Every top track is another one, the last played track stays
+            if idle_screen != Some(screen) || (screen == IdleScreen::TopTracks && advanced) {
+                idle_screen = Some(screen);
+                curr_playing = None;
+                track_text = None;
+                time_labels.clear();
+                curr_palette = theme.palette;
+                curr_backdrop = None;
+                curr_cover = None;
+                cover_transition = None;
+                volume_overlay = None;
+                lyrics_view = None;
+
+                let track = match &top_track {
+                    Some(track) => Some((track, None)),
+                    None => curr_last_played
+                        .as_ref()
+                        .map(|last_played| (&last_played.track, last_played.context.as_ref())),
+                };
+                match track {
+                    Some((track, context)) => {
+                        // Cover follows with `Message::UpdateArtwork` like for a playing track
+                        let generation =
+                            idle_artwork_generation.fetch_add(1, Ordering::Relaxed) + 1;
+                        idle_artwork_sender
+                            .send(ArtworkRequest {
+                                generation,
+                                name: track.name.clone(),
+                                url: track.image_url.clone(),
+                                album: context.and_then(Context::album_name).map(str::to_string),
+                            })
+                            .unwrap();
+
+                        let text = track_text.insert(TrackText::from_track(
+                            track,
+                            context,
+                            MARQUEE_STYLE,
+                            &layout,
+                            &theme,
+                        ));
+                        graphics::draw_album_cover(
+                            &mut display,
+                            &layout,
+                            None,
+                            curr_palette,
+                            None,
+                            PANEL_FORMAT,
+                        );
+                        graphics::draw_current_name_and_artist(
+                            &mut display,
+                            &layout,
+                            text,
+                            curr_palette,
+                            None,
+                            PANEL_FORMAT,
+                        );
+                    }
+                    None => graphics::draw_no_song(&mut display, &theme, PANEL_FORMAT),
+                }
+            }
+            draw_idle_labels(
+                &mut display,
+                &layout,
+                &theme,
+                screen,
+                curr_last_played.as_ref(),
+                top_tracks.as_ref(),
+                curr_palette,
+                curr_backdrop.as_ref(),
+            );
+        }
+
+        if volume_overlay
+            .as_ref()
+            .is_some_and(VolumeOverlay::is_finished)
+        {
+            volume_overlay.take().unwrap().restore(&mut display);
+        }
+
+        for area in display.take_dirty() {
+            match &mut panel {
+                Some(panel) => {
+                    let started_at = Instant::now();
+                    flush_buf.clear();
+                    display.copy_area(&area, &mut flush_buf);
+                    panel.flush(area, &flush_buf);
+                    log::debug!("Flushed {:?} in {:?}", area.size, started_at.elapsed());
+                }
+                None => {
+                    // Waits for the flush thread to finish with a buffer
+                    let mut buffer = free_receiver.recv().unwrap();
+                    buffer.clear();
+                    display.copy_area(&area, &mut buffer);
+                    flush_sender.send((area, buffer)).unwrap();
+                }
+            }
+        }
+
         // 1ms delay every iteration to make sure good ol watchdog gets fed
         Delay::new_default().delay_ms(1);
     }
 }
 
-fn get_playing(client: &mut EspHttpConnection, res_buf: &mut [u8]) -> Result<Option<Playing>, ()> {
-    log::info!("Getting currently playing...");
-    client
-        .initiate_request(
-            esp_idf_svc::http::Method::Get,
-            "https://6q7btxffqgoyulwyg4jktyayzu0kvcyf.lambda-url.us-east-1.on.aws/playing",
-            &[],
-        )
-        .unwrap();
+/// Whatever goes around the track while nothing is playing, drawn again whenever its colors change
+#[allow(clippy::too_many_arguments)]
+fn draw_idle_labels(
+    display: &mut Framebuffer,
+    layout: &Layout,
+    theme: &Theme,
+    screen: IdleScreen,
+    last_played: Option<&LastPlayed>,
+    top_tracks: Option<&TopTracks>,
+    palette: Palette,
+    backdrop: Option<&Backdrop>,
+) {
+    match (screen, last_played, top_tracks) {
+        (IdleScreen::LastPlayed, Some(last_played), _) => graphics::draw_last_played(
+            display,
+            layout,
+            theme,
+            last_played.secs_ago(),
+            palette,
+            backdrop,
+            PANEL_FORMAT,
+        ),
+        (IdleScreen::TopTracks, _, Some(top_tracks)) => graphics::draw_top_track(
+            display,
+            layout,
+            theme,
+            top_tracks,
+            palette,
+            backdrop,
+            PANEL_FORMAT,
+        ),
+        _ => {}
+    }
+}
 
-    client.initiate_response().unwrap();
+/// GETs `path` from my API and deserializes the JSON response
+fn get_json<T: DeserializeOwned>(
+    path: &str,
+    client: &mut EspHttpConnection,
+    res_buf: &mut [u8],
+) -> Result<T, RequestError> {
+    log::info!("Getting {path}...");
+    client.initiate_request(
+        esp_idf_svc::http::Method::Get,
+        &format!("{API_URL}{path}"),
+        &[],
+    )?;
 
-    let length: usize = client
-        .header("content-length")
-        .and_then(|s| s.parse().ok())
-        .unwrap();
+    client.initiate_response()?;
+
+    let read = read_response(client, res_buf, &|| false)?.unwrap();
+
+    log::info!("Deserializing res...");
+    serde_json::from_slice(&res_buf[..read]).map_err(RequestError::Json)
+}
 
+/// Reads the body of the response `client` got into `buf` and returns its length, or `None` if
+/// `is_cancelled` before it was read completely
+///
+/// The connection can't be used again after a cancelled read.
+fn read_response(
+    client: &mut EspHttpConnection,
+    buf: &mut [u8],
+    is_cancelled: &impl Fn() -> bool,
+) -> Result<Option<usize>, RequestError> {
+    let length: Option<usize> = client.header("content-length").and_then(|s| s.parse().ok());
+    if let Some(length) = length.filter(|length| *length > buf.len()) {
+        // Read it anyway, the connection is used for the next request
+        let mut read = 0;
+        while read < length {
+            match client.read(buf)? {
+                0 => return Err(RequestError::Truncated),
+                len => read += len,
+            }
+        }
+        return Err(RequestError::TooLarge(Some(length)));
+    }
+
+    // Without a length the response ends once nothing more can be read
     let mut read = 0;
-    while read < length {
-        read += client.read(&mut res_buf[read..]).unwrap();
+    while length.map_or(true, |length| read < length) {
+        if is_cancelled() {
+            return Ok(None);
+        }
+        if read == buf.len() {
+            return Err(RequestError::TooLarge(None));
+        }
+        match client.read(&mut buf[read..])? {
+            0 if length.is_some() => return Err(RequestError::Truncated),
+            0 => break,
+            len => read += len,
+        }
     }
 
     if !(200..300).contains(&client.status()) {
-        log::error!("Bad status requesting current playing: {}", client.status());
-        log::error!(
-            "Response: {}",
-            std::str::from_utf8(&res_buf[..read]).unwrap()
-        );
+        log::error!("Response: {}", String::from_utf8_lossy(&buf[..read]));
 
-        return Err(());
+        return Err(RequestError::Status(client.status()));
     }
 
-    log::info!("Deserializing res...");
-    Ok(serde_json::from_slice(&res_buf[..read]).unwrap())
+    Ok(Some(read))
 }
 
-fn get_image(url: &str, client: &mut EspHttpConnection, image_buf: &mut [u8]) -> Arc<[u8]> {
-    client
-        .initiate_request(esp_idf_svc::http::Method::Get, url, &[])
-        .unwrap();
+/// Logs why getting `path` failed, and opens a new connection if the old one can't be used
+fn request_failed(path: &str, err: RequestError, client: &mut EspHttpConnection) {
+    log::error!("Getting {path} failed: {err}");
+
+    if err.breaks_connection() {
+        *client = new_api_client();
+    }
+}
 
-    client.initiate_response().unwrap();
+fn new_api_client() -> EspHttpConnection {
+    EspHttpConnection::new(&Configuration {
+        crt_bundle_attach: Some(esp_crt_bundle_attach),
+        ..Default::default()
+    })
+    .unwrap()
+}
 
-    let length = client
-        .header("content-length")
-        .unwrap()
-        .parse::<usize>()
-        .unwrap();
+fn get_image(
+    url: &str,
+    client: &mut EspHttpConnection,
+    image_buf: &mut [u8],
+    layout: &Layout,
+    theme: &Theme,
+    dither: Dither,
+    backdrop: bool,
+    is_cancelled: &impl Fn() -> bool,
+) -> Result<Option<Artwork>, RequestError> {
+    client.initiate_request(esp_idf_svc::http::Method::Get, url, &[])?;
+
+    client.initiate_response()?;
 
     let image_type = client
         .header("content-type")
-        .unwrap()
-        .split_once("/")
-        .unwrap()
-        .1
-        .to_string();
+        .and_then(|content_type| content_type.split_once('/'))
+        .map(|(_, image_type)| image_type.to_string())
+        .unwrap_or_default();
 
-    println!("img type: {image_type}; length: {length}");
-    let mut read = 0;
-    while read < length {
-        read += client.read(&mut image_buf[read..]).unwrap();
-    }
+    let Some(read) = read_response(client, image_buf, is_cancelled)? else {
+        // Leaves the response unread, caller has to drop this connection
+        return Ok(None);
+    };
+    log::info!("Got {read} bytes of {image_type}");
 
     log::info!("Decoding image.");
     let image = match image_type.as_str() {
-        "jpeg" | "jpg" => {
-            let mut decoder = jpeg_decoder::Decoder::new(BufReader::new(&image_buf[..read]));
-            decoder.read_info().unwrap();
-            let info = decoder.info().unwrap();
-
-            DynamicImage::ImageRgb8(
-                ImageBuffer::from_vec(
-                    info.width as u32,
-                    info.height as u32,
-                    decoder.decode().unwrap(),
-                )
-                .unwrap(),
-            )
-        }
-        _ => panic!("Unsupported image type: {image_type}"),
+        "jpeg" | "jpg" => decode_jpeg(&image_buf[..read])?,
+        _ => return Err(RequestError::UnsupportedImage(image_type)),
     };
 
+    if is_cancelled() {
+        return Ok(None);
+    }
+
     log::info!("Decoded image, resizing.");
     let rgb8_image = image
         .resize(
-            IMAGE_WIDTH,
-            IMAGE_WIDTH,
+            layout.cover.size.width,
+            layout.cover.size.width,
             image::imageops::FilterType::Triangle,
         )
         .into_rgb8();
 
-    Arc::from(graphics::rgb8_to_rgb565(&rgb8_image))
+    if is_cancelled() {
+        return Ok(None);
+    }
+
+    Ok(Some(create_artwork(
+        &rgb8_image,
+        layout,
+        theme,
+        dither,
+        backdrop,
+    )))
+}
+
+fn decode_jpeg(jpeg: &[u8]) -> Result<DynamicImage, RequestError> {
+    let mut decoder = jpeg_decoder::Decoder::new(BufReader::new(jpeg));
+    decoder.read_info().map_err(RequestError::Jpeg)?;
+    let info = decoder.info().unwrap();
+    let pixels = decoder.decode().map_err(RequestError::Jpeg)?;
+    let (width, height) = (info.width as u32, info.height as u32);
+
+    let image = match info.pixel_format {
+        jpeg_decoder::PixelFormat::RGB24 => {
+            ImageBuffer::from_vec(width, height, pixels).map(DynamicImage::ImageRgb8)
+        }
+        jpeg_decoder::PixelFormat::L8 => {
+            ImageBuffer::from_vec(width, height, pixels).map(DynamicImage::ImageLuma8)
+        }
+        _ => None,
+    };
+    image.ok_or_else(|| RequestError::UnsupportedImage(format!("{:?} jpeg", info.pixel_format)))
+}
+
+fn create_artwork(
+    rgb8_image: &RgbImage,
+    layout: &Layout,
+    theme: &Theme,
+    dither: Dither,
+    backdrop: bool,
+) -> Artwork {
+    let artwork = Artwork::new(rgb8_image, dither);
+    if backdrop {
+        artwork.with_backdrop(rgb8_image, layout.screen.size, theme)
+    } else {
+        artwork
+    }
 }
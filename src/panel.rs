@@ -0,0 +1,122 @@
+use embedded_graphics::primitives::Rectangle;
+use esp_idf_svc::hal::{
+    delay::Delay,
+    gpio::{Output, Pin, PinDriver},
+    spi::{SpiDeviceDriver, SpiDriver},
+};
+use graphics::Orientation;
+
+const SWRESET: u8 = 0x01;
+const SLPOUT: u8 = 0x11;
+const INVOFF: u8 = 0x20;
+const DISPON: u8 = 0x29;
+const CASET: u8 = 0x2a;
+const RASET: u8 = 0x2b;
+const RAMWR: u8 = 0x2c;
+const MADCTL: u8 = 0x36;
+const COLMOD: u8 = 0x3a;
+const FRMCTR1: u8 = 0xb1;
+const FRMCTR2: u8 = 0xb2;
+const FRMCTR3: u8 = 0xb3;
+const INVCTR: u8 = 0xb4;
+const PWCTR1: u8 = 0xc0;
+const PWCTR2: u8 = 0xc1;
+const PWCTR3: u8 = 0xc2;
+const PWCTR4: u8 = 0xc3;
+const PWCTR5: u8 = 0xc4;
+const VMCTR1: u8 = 0xc5;
+
+/// ST7735 driven with raw SPI writes, so a whole framebuffer can go out in a single DMA transfer
+///
+/// Colors are sent as-is, `PANEL_FORMAT` takes care of color order and inversion.
+pub struct Panel<'d, DC: Pin, RST: Pin> {
+    spi: SpiDeviceDriver<'d, SpiDriver<'d>>,
+    dc: PinDriver<'d, DC, Output>,
+    _rst: PinDriver<'d, RST, Output>,
+}
+
+impl<'d, DC: Pin, RST: Pin> Panel<'d, DC, RST> {
+    /// Resets and initializes the panel, same sequence as the `st7735-lcd` crate
+    ///
+    /// In [`Orientation::Landscape`] rows and columns are swapped, so the width becomes the height.
+    pub fn new(
+        spi: SpiDeviceDriver<'d, SpiDriver<'d>>,
+        dc: PinDriver<'d, DC, Output>,
+        mut rst: PinDriver<'d, RST, Output>,
+        orientation: Orientation,
+    ) -> Self {
+        let delay = Delay::new_default();
+        rst.set_high().unwrap();
+        delay.delay_ms(10);
+        rst.set_low().unwrap();
+        delay.delay_ms(10);
+        rst.set_high().unwrap();
+        delay.delay_ms(10);
+
+        let mut panel = Self { spi, dc, _rst: rst };
+        panel.command(SWRESET, &[]);
+        delay.delay_ms(200);
+        panel.command(SLPOUT, &[]);
+        delay.delay_ms(200);
+
+        panel.command(FRMCTR1, &[0x01, 0x2c, 0x2d]);
+        panel.command(FRMCTR2, &[0x01, 0x2c, 0x2d]);
+        panel.command(FRMCTR3, &[0x01, 0x2c, 0x2d, 0x01, 0x2c, 0x2d]);
+        panel.command(INVCTR, &[0x07]);
+        panel.command(PWCTR1, &[0xa2, 0x02, 0x84]);
+        panel.command(PWCTR2, &[0xc5]);
+        panel.command(PWCTR3, &[0x0a, 0x00]);
+        panel.command(PWCTR4, &[0x8a, 0x2a]);
+        panel.command(PWCTR5, &[0x8a, 0xee]);
+        panel.command(VMCTR1, &[0x0e]);
+        // `PANEL_FORMAT` inverts colors while converting them for inverted panels
+        panel.command(INVOFF, &[]);
+        // RGB order, landscape swaps rows and columns and mirrors the columns
+        panel.command(
+            MADCTL,
+            &[match orientation {
+                Orientation::Portrait => 0x00,
+                Orientation::Landscape => 0x60,
+            }],
+        );
+        // 16 bits per pixel
+        panel.command(COLMOD, &[0x05]);
+        panel.command(DISPON, &[]);
+        delay.delay_ms(200);
+
+        panel
+    }
+
+    fn command(&mut self, command: u8, params: &[u8]) {
+        self.dc.set_low().unwrap();
+        self.spi.write(&[command]).unwrap();
+
+        if !params.is_empty() {
+            self.dc.set_high().unwrap();
+            self.spi.write(params).unwrap();
+        }
+    }
+
+    /// Sends `bytes` created by [`graphics::Framebuffer`] to `area`, the SPI driver splits them into
+    /// DMA transfers of at most its maximum transfer size
+    pub fn flush(&mut self, area: Rectangle, bytes: &[u8]) {
+        let Some(bottom_right) = area.bottom_right() else {
+            return;
+        };
+
+        let [x_start, x_end, y_start, y_end] = [
+            area.top_left.x,
+            bottom_right.x,
+            area.top_left.y,
+            bottom_right.y,
+        ]
+        .map(|value| (value as u16).to_be_bytes());
+
+        self.command(CASET, &[x_start[0], x_start[1], x_end[0], x_end[1]]);
+        self.command(RASET, &[y_start[0], y_start[1], y_end[0], y_end[1]]);
+        self.command(RAMWR, &[]);
+
+        self.dc.set_high().unwrap();
+        self.spi.write(bytes).unwrap();
+    }
+}